@@ -1,15 +1,12 @@
 #[macro_use] extern crate log;
 use color_eyre::{Report, Result};
 use futures::StreamExt;
-use k8s_openapi::{
-    api::core::v1::ConfigMap,
-    apimachinery::pkg::apis::meta::v1::{ObjectMeta, OwnerReference},
-};
+use k8s_openapi::{api::core::v1::ConfigMap, apimachinery::pkg::apis::meta::v1::ObjectMeta};
 use kube::{
     api::{ListParams, Patch, PatchParams, Resource},
     Api, Client, CustomResource,
 };
-use kube_runtime::controller::{Context, Controller, ReconcilerAction};
+use kube_runtime::controller::{Context, Controller, ReconcileReason, ReconcilerAction};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
@@ -36,35 +33,24 @@ struct ConfigMapGeneratorSpec {
     content: String,
 }
 
-fn object_to_owner_reference<K: Resource<DynamicType = ()>>(
-    meta: ObjectMeta,
-) -> Result<OwnerReference, Error> {
-    Ok(OwnerReference {
-        api_version: K::api_version(&()).to_string(),
-        kind: K::kind(&()).to_string(),
-        name: meta.name.context(MissingObjectKey {
-            name: ".metadata.name",
-        })?,
-        uid: meta.uid.context(MissingObjectKey {
-            name: ".metadata.uid",
-        })?,
-        ..OwnerReference::default()
-    })
-}
-
 /// Controller triggers this whenever our main object or our children changed
-async fn reconcile(generator: ConfigMapGenerator, ctx: Context<Data>) -> Result<ReconcilerAction, Error> {
+async fn reconcile(
+    generator: ConfigMapGenerator,
+    _reason: ReconcileReason,
+    ctx: Context<Data>,
+) -> Result<ReconcilerAction, Error> {
     let client = ctx.get_ref().client.clone();
 
     let mut contents = BTreeMap::new();
-    contents.insert("content".to_string(), generator.spec.content);
+    contents.insert("content".to_string(), generator.spec.content.clone());
     let cm = ConfigMap {
         metadata: ObjectMeta {
             name: generator.metadata.name.clone(),
-            owner_references: Some(vec![OwnerReference {
-                controller: Some(true),
-                ..object_to_owner_reference::<ConfigMapGenerator>(generator.metadata.clone())?
-            }]),
+            owner_references: Some(vec![generator.controller_owner_ref(&()).context(
+                MissingObjectKey {
+                    name: ".metadata.uid",
+                },
+            )?]),
             ..ObjectMeta::default()
         },
         data: Some(contents),
@@ -92,7 +78,7 @@ async fn reconcile(generator: ConfigMapGenerator, ctx: Context<Data>) -> Result<
 }
 
 /// The controller triggers this on reconcile errors
-fn error_policy(_error: &Error, _ctx: Context<Data>) -> ReconcilerAction {
+fn error_policy(_error: &Error, _reason: &ReconcileReason, _ctx: Context<Data>) -> ReconcilerAction {
     ReconcilerAction {
         requeue_after: Some(Duration::from_secs(1)),
     }
@@ -7,7 +7,7 @@ use apiexts::CustomResourceDefinition;
 use k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1 as apiexts;
 
 use kube::{
-    api::{Api, ListParams, Patch, PatchParams, ResourceExt, WatchEvent},
+    api::{Api, Patch, PatchParams, ResourceExt, WatchEvent, WatchParams},
     Client, CustomResource,
 };
 
@@ -83,10 +83,10 @@ async fn wait_for_crd_ready(crds: &Api<CustomResourceDefinition>) -> anyhow::Res
         return Ok(());
     }
     // Wait for the apply to take place (takes a sec or two during first install)
-    let lp = ListParams::default()
+    let wp = WatchParams::default()
         .fields(&format!("metadata.name={}", "foos.clux.dev")) // our crd only
         .timeout(5); // should not take long
-    let mut stream = crds.watch(&lp, "0").await?.boxed();
+    let mut stream = crds.watch(&wp, "0").await?.boxed();
 
     while let Some(status) = stream.try_next().await? {
         if let WatchEvent::Modified(s) = status {
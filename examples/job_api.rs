@@ -4,7 +4,7 @@ use k8s_openapi::api::batch::v1::Job;
 use serde_json::json;
 
 use kube::{
-    api::{Api, DeleteParams, ListParams, PostParams, ResourceExt, WatchEvent},
+    api::{Api, DeleteParams, PostParams, ResourceExt, WatchEvent, WatchParams},
     Client,
 };
 
@@ -45,10 +45,10 @@ async fn main() -> anyhow::Result<()> {
     jobs.create(&pp, &my_job).await?;
 
     // See if it ran to completion
-    let lp = ListParams::default()
+    let wp = WatchParams::default()
         .fields(&format!("metadata.name={}", job_name)) // only want events for our job
         .timeout(20); // should be done by then
-    let mut stream = jobs.watch(&lp, "").await?.boxed();
+    let mut stream = jobs.watch(&wp, "").await?.boxed();
 
     while let Some(status) = stream.try_next().await? {
         match status {
@@ -4,7 +4,9 @@ use k8s_openapi::api::core::v1::Pod;
 use serde_json::json;
 
 use kube::{
-    api::{Api, DeleteParams, ListParams, Patch, PatchParams, PostParams, ResourceExt, WatchEvent},
+    api::{
+        Api, DeleteParams, ListParams, Patch, PatchParams, PostParams, ResourceExt, WatchEvent, WatchParams,
+    },
     Client,
 };
 
@@ -46,10 +48,10 @@ async fn main() -> anyhow::Result<()> {
     }
 
     // Watch it phase for a few seconds
-    let lp = ListParams::default()
+    let wp = WatchParams::default()
         .fields(&format!("metadata.name={}", "blog"))
         .timeout(10);
-    let mut stream = pods.watch(&lp, "0").await?.boxed();
+    let mut stream = pods.watch(&wp, "0").await?.boxed();
     while let Some(status) = stream.try_next().await? {
         match status {
             WatchEvent::Added(o) => info!("Added {}", o.name()),
@@ -0,0 +1,395 @@
+//! Controller over a changing set of kinds, for example every kind that is served by the cluster
+use super::{applier, trigger_self, Context, Error, ReconcileReason, ReconcileRequest, ReconcilerAction};
+use crate::{
+    reflector::{store::Writer, ObjectRef, Store},
+    utils::{try_flatten_applied, CancelableJoinHandle},
+    watcher::{self, watcher},
+};
+use dashmap::DashMap;
+use futures::{
+    channel::oneshot,
+    stream::{self, BoxStream, Fuse, SelectAll},
+    Stream, StreamExt, TryFuture, TryFutureExt, TryStreamExt,
+};
+use kube::{
+    api::{Api, ApiResource, DynamicObject, ListParams, TypeMeta},
+    client::discovery::{verbs, ApiResourceExtras, Discovery},
+    Client,
+};
+use snafu::{Backtrace, IntoError, Snafu};
+use std::{
+    collections::{HashMap, HashSet},
+    pin::Pin,
+    sync::Arc,
+    task::{Context as TaskContext, Poll},
+    time::Duration,
+};
+use tokio::runtime::Handle;
+
+#[derive(Snafu, Debug)]
+pub enum DynamicQueueError {
+    #[snafu(display("failed to discover kinds to watch: {}", source))]
+    DiscoveryFailed {
+        source: kube::Error,
+        backtrace: Backtrace,
+    },
+    #[snafu(display("failed to watch {}.{}: {}", resource.kind, resource.api_version, source))]
+    WatchFailed {
+        resource: ApiResource,
+        #[snafu(backtrace)]
+        source: watcher::Error,
+    },
+}
+
+/// A controller for [`DynamicObject`]s of several kinds, which may change while it is running
+///
+/// Each update to the `kinds` stream replaces the set of kinds to watch: a watch is started for every new kind,
+/// and the watches (and cached objects) of the kinds that are no longer in the set are dropped. All objects
+/// are reconciled by the same reconciler, which is also given the [`ApiResource`] of the object.
+///
+/// [`DynamicController::discover`] keeps the set of kinds up to date using [`Discovery`], so that
+/// kinds are picked up (or dropped) as `CustomResourceDefinition`s are installed (or removed).
+///
+/// ```no_run
+/// use futures::StreamExt;
+/// use kube::{api::{DynamicObject, ApiResource, ListParams}, client::discovery::Scope, Client};
+/// use kube_runtime::controller::{Context, DynamicController, ReconcileReason, ReconcilerAction};
+/// use std::{convert::Infallible, time::Duration};
+///
+/// async fn reconcile(obj: DynamicObject, resource: ApiResource, _reason: ReconcileReason, _ctx: Context<()>) -> Result<ReconcilerAction, Infallible> {
+///     println!("checking {} {}", resource.kind, obj.metadata.name.unwrap());
+///     Ok(ReconcilerAction { requeue_after: None })
+/// }
+/// fn error_policy(err: &Infallible, _reason: &ReconcileReason, _ctx: Context<()>) -> ReconcilerAction {
+///     match *err {}
+/// }
+///
+/// # async fn wrapper() -> Result<(), kube::Error> {
+/// let client = Client::try_default().await?;
+/// // Every namespaced kind, but only objects that opt into the policy
+/// DynamicController::discover(
+///     client,
+///     ListParams::default().labels("policy.example.com/enforce=true"),
+///     |_resource, extras| extras.scope == Scope::Namespaced,
+///     Duration::from_secs(60),
+/// )
+/// .run(reconcile, error_policy, Context::new(()))
+/// .for_each(|res| async move {
+///     match res {
+///         Ok(o) => println!("reconciled {:?}", o),
+///         Err(e) => println!("reconcile failed: {}", e),
+///     }
+/// })
+/// .await;
+/// # Ok(())
+/// # }
+/// ```
+pub struct DynamicController {
+    client: Client,
+    lp: ListParams,
+    kinds: BoxStream<'static, kube::Result<Vec<ApiResource>>>,
+    reader: Store<DynamicObject>,
+}
+
+impl DynamicController {
+    /// Create a controller that watches the kinds emitted by `kinds`
+    ///
+    /// All kinds are watched across all namespaces, filtered by `lp`.
+    pub fn new(
+        client: Client,
+        lp: ListParams,
+        kinds: impl Stream<Item = kube::Result<Vec<ApiResource>>> + Send + 'static,
+    ) -> Self {
+        DynamicController {
+            client,
+            lp,
+            kinds: kinds.boxed(),
+            reader: Store::empty(),
+        }
+    }
+
+    /// Create a controller that watches every kind that is accepted by `filter`
+    ///
+    /// Only the preferred version of each group is considered, and only kinds that support
+    /// listing and watching are passed to `filter`. The cluster is rediscovered every `interval`.
+    pub fn discover(
+        client: Client,
+        lp: ListParams,
+        filter: impl Fn(&ApiResource, &ApiResourceExtras) -> bool + Send + Sync + 'static,
+        interval: Duration,
+    ) -> Self {
+        let filter = Arc::new(filter);
+        let discovery_client = client.clone();
+        let kinds = stream::unfold(true, move |first| {
+            let client = discovery_client.clone();
+            let filter = filter.clone();
+            async move {
+                if !first {
+                    tokio::time::sleep(interval).await;
+                }
+                let kinds = Discovery::new(&client)
+                    .await
+                    .map(|discovery| discovered_kinds(&discovery, &*filter));
+                Some((kinds, false))
+            }
+        });
+        Self::new(client, lp, kinds)
+    }
+
+    /// Retrieve a copy of the reader before starting the controller
+    ///
+    /// The store contains the objects of all kinds that are currently being watched.
+    #[must_use]
+    pub fn store(&self) -> Store<DynamicObject> {
+        self.reader.clone()
+    }
+
+    /// Consume the controller and start the applier stream
+    ///
+    /// This works like [`Controller::run`](super::Controller::run), except that the `reconciler`
+    /// is also passed the [`ApiResource`] of each object.
+    ///
+    /// # Panics
+    ///
+    /// The returned stream panics if it is asked to reconcile an object whose kind is not being watched.
+    /// This should never happen, since only objects of watched kinds are stored.
+    pub fn run<ReconcilerFut, T>(
+        self,
+        mut reconciler: impl FnMut(DynamicObject, ApiResource, ReconcileReason, Context<T>) -> ReconcilerFut,
+        error_policy: impl FnMut(&ReconcilerFut::Error, &ReconcileReason, Context<T>) -> ReconcilerAction,
+        context: Context<T>,
+    ) -> impl Stream<
+        Item = Result<
+            (ObjectRef<DynamicObject>, ReconcilerAction),
+            Error<ReconcilerFut::Error, DynamicQueueError>,
+        >,
+    >
+    where
+        ReconcilerFut: TryFuture<Ok = ReconcilerAction> + Send + 'static,
+        ReconcilerFut::Error: std::error::Error + Send + 'static,
+    {
+        let resources = Arc::new(DashMap::new());
+        let queue = KindWatches {
+            kinds: self.kinds.fuse(),
+            watches: SelectAll::new(),
+            active: HashMap::new(),
+            client: self.client,
+            lp: self.lp,
+            store: self.reader.clone(),
+            resources: resources.clone(),
+        };
+        applier(
+            move |obj: DynamicObject, reason, ctx| {
+                // Every stored object has its types filled in by `KindWatches`
+                let types = obj.types.as_ref().expect("stored objects must have types");
+                let resource = resources
+                    .get(&(types.api_version.clone(), types.kind.clone()))
+                    .expect("stored objects must have a watched kind")
+                    .clone();
+                CancelableJoinHandle::spawn(
+                    reconciler(obj, resource, reason, ctx).into_future(),
+                    &Handle::current(),
+                )
+            },
+            error_policy,
+            context,
+            self.reader,
+            queue,
+        )
+    }
+}
+
+/// Picks the kinds to watch out of `discovery`
+fn discovered_kinds(
+    discovery: &Discovery,
+    filter: &dyn Fn(&ApiResource, &ApiResourceExtras) -> bool,
+) -> Vec<ApiResource> {
+    discovery
+        .groups()
+        .flat_map(|group| group.resources_by_version(group.preferred_version_or_guess()))
+        .filter(|(resource, extras)| {
+            extras.supports_operation(verbs::LIST)
+                && extras.supports_operation(verbs::WATCH)
+                && filter(resource, extras)
+        })
+        .map(|(resource, _)| resource)
+        .collect()
+}
+
+/// Watches for the active set of kinds, updating it as the `kinds` stream changes
+struct KindWatches {
+    kinds: Fuse<BoxStream<'static, kube::Result<Vec<ApiResource>>>>,
+    watches: SelectAll<BoxStream<'static, Result<ReconcileRequest<DynamicObject>, DynamicQueueError>>>,
+    /// Dropping the `Sender` stops the kind's watch
+    active: HashMap<ApiResource, (oneshot::Sender<()>, Writer<DynamicObject>)>,
+    client: Client,
+    lp: ListParams,
+    store: Store<DynamicObject>,
+    /// Resolves the `(apiVersion, kind)` of stored objects, never shrinks
+    resources: Arc<DashMap<(String, String), ApiResource>>,
+}
+
+impl KindWatches {
+    fn start_watch(&mut self, resource: ApiResource) {
+        let (stop_tx, stop_rx) = oneshot::channel();
+        let mut writer = self.store.writer_for(resource.clone());
+        let types = TypeMeta {
+            api_version: resource.api_version.clone(),
+            kind: resource.kind.clone(),
+        };
+        let err_resource = resource.clone();
+        let events = watcher(Api::all_with(self.client.clone(), &resource), self.lp.clone())
+            .map_ok(move |mut event: watcher::Event<DynamicObject>| {
+                // Objects in lists don't necessarily include their types
+                match &mut event {
+                    watcher::Event::Applied(obj) | watcher::Event::Deleted(obj) => {
+                        obj.types = Some(types.clone());
+                    }
+                    watcher::Event::Restarted(objs) => {
+                        for obj in objs {
+                            obj.types = Some(types.clone());
+                        }
+                    }
+                }
+                writer.apply_watcher_event(&event);
+                event
+            })
+            .map_err(move |source| {
+                WatchFailed {
+                    resource: err_resource.clone(),
+                }
+                .into_error(source)
+            });
+        let watch = trigger_self(try_flatten_applied(events), resource.clone())
+            .take_until(stop_rx)
+            .boxed();
+        self.resources.insert(
+            (resource.api_version.clone(), resource.kind.clone()),
+            resource.clone(),
+        );
+        self.watches.push(watch);
+        self.active
+            .insert(resource.clone(), (stop_tx, self.store.writer_for(resource)));
+    }
+
+    fn set_kinds(&mut self, kinds: Vec<ApiResource>) {
+        let kinds = kinds.into_iter().collect::<HashSet<_>>();
+        let removed = self
+            .active
+            .keys()
+            .filter(|kind| !kinds.contains(kind))
+            .cloned()
+            .collect::<Vec<_>>();
+        for kind in removed {
+            if let Some((_stop_tx, mut writer)) = self.active.remove(&kind) {
+                // Only clears objects of this kind, other kinds have their own writers
+                writer.apply_watcher_event(&watcher::Event::Restarted(Vec::new()));
+            }
+        }
+        for kind in kinds {
+            if !self.active.contains_key(&kind) {
+                self.start_watch(kind);
+            }
+        }
+    }
+}
+
+impl Stream for KindWatches {
+    type Item = Result<ReconcileRequest<DynamicObject>, DynamicQueueError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            match this.kinds.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(kinds))) => this.set_kinds(kinds),
+                Poll::Ready(Some(Err(source))) => {
+                    return Poll::Ready(Some(Err(DiscoveryFailed.into_error(source))));
+                }
+                Poll::Ready(None) | Poll::Pending => break,
+            }
+        }
+        match this.watches.poll_next_unpin(cx) {
+            // `SelectAll` ends whenever it is empty, but more kinds may still be added later
+            Poll::Ready(None) if !this.kinds.is_done() => Poll::Pending,
+            poll => poll,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DynamicController;
+    use crate::{
+        controller::{Context, ReconcilerAction},
+        reflector::ObjectRef,
+    };
+    use futures::{channel::mpsc, StreamExt};
+    use k8s_openapi::api::core::v1::{ConfigMap, Secret};
+    use kube::{
+        api::{ApiResource, ListParams, ObjectMeta, ResourceExt},
+        fake::FakeApiServer,
+    };
+    use std::{collections::HashSet, convert::Infallible, time::Duration};
+
+    #[tokio::test]
+    async fn dynamic_controller_should_follow_kind_changes() {
+        let server = FakeApiServer::new();
+        let meta = |name: &str| ObjectMeta {
+            name: Some(name.to_string()),
+            namespace: Some("ns".to_string()),
+            ..ObjectMeta::default()
+        };
+        server.insert(&ConfigMap {
+            metadata: meta("cm"),
+            ..ConfigMap::default()
+        });
+        server.insert(&Secret {
+            metadata: meta("secret"),
+            ..Secret::default()
+        });
+        let cm_resource = ApiResource::erase::<ConfigMap>(&());
+        let secret_resource = ApiResource::erase::<Secret>(&());
+
+        let (kinds_tx, kinds_rx) = mpsc::unbounded();
+        kinds_tx
+            .unbounded_send(Ok(vec![cm_resource.clone(), secret_resource.clone()]))
+            .unwrap();
+        let controller = DynamicController::new(server.client(), ListParams::default(), kinds_rx);
+        let store = controller.store();
+        let mut applier = controller
+            .run(
+                |obj, resource, _reason, _ctx| async move {
+                    assert_eq!(obj.types.unwrap().kind, resource.kind);
+                    Ok::<_, Infallible>(ReconcilerAction { requeue_after: None })
+                },
+                |err, _reason, _ctx| match *err {},
+                Context::new(()),
+            )
+            .boxed();
+
+        let mut reconciled = HashSet::new();
+        for _ in 0..2 {
+            let (obj_ref, _) = applier.next().await.unwrap().unwrap();
+            reconciled.insert(obj_ref);
+        }
+        assert_eq!(
+            reconciled,
+            vec![
+                ObjectRef::new_with("cm", cm_resource.clone()).within("ns"),
+                ObjectRef::new_with("secret", secret_resource).within("ns"),
+            ]
+            .into_iter()
+            .collect()
+        );
+
+        kinds_tx.unbounded_send(Ok(vec![cm_resource])).unwrap();
+        // Nothing is left to reconcile, but polling picks up the new set of kinds
+        assert!(tokio::time::timeout(Duration::from_millis(100), applier.next())
+            .await
+            .is_err());
+        assert_eq!(
+            store.state().iter().map(ResourceExt::name).collect::<Vec<_>>(),
+            vec!["cm"]
+        );
+    }
+}
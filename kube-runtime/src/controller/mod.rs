@@ -1,13 +1,19 @@
 //! Runs a user-supplied reconciler function on objects when they (or related objects) are updated
 
-use self::runner::Runner;
+use self::runner::{Runner, RunningMessages};
+pub use self::{
+    dynamic::{DynamicController, DynamicQueueError},
+    multi_cluster::{ClusterClient, MultiClusterController, MultiClusterQueueError},
+    prune::{prune_orphans, prune_orphans_with},
+};
 use crate::{
     reflector::{
+        index::ReferenceIndex,
         reflector,
         store::{Store, Writer},
         ObjectRef,
     },
-    scheduler::{self, scheduler, ScheduleRequest},
+    scheduler::{self, scheduler_with_inspector, ScheduleRequest, SchedulerInspector},
     utils::{try_flatten_applied, try_flatten_touched, trystream_try_via, CancelableJoinHandle},
     watcher::{self, watcher},
 };
@@ -20,11 +26,24 @@ use futures::{
 use kube::api::{Api, DynamicObject, ListParams, Resource};
 use serde::de::DeserializeOwned;
 use snafu::{futures::TryStreamExt as SnafuTryStreamExt, Backtrace, ResultExt, Snafu};
-use std::{fmt::Debug, hash::Hash, sync::Arc, time::Duration};
+use std::{
+    collections::hash_map::DefaultHasher,
+    convert::TryFrom,
+    fmt::{self, Debug, Display},
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 use stream::BoxStream;
-use tokio::{runtime::Handle, time::Instant};
+use tokio::{
+    runtime::Handle,
+    time::{self, Instant},
+};
 
+mod dynamic;
 mod future_hash_map;
+mod multi_cluster;
+pub mod prune;
 mod runner;
 
 #[derive(Snafu, Debug)]
@@ -57,18 +76,97 @@ pub struct ReconcilerAction {
     pub requeue_after: Option<Duration>,
 }
 
+/// Why a reconciliation was requested
+///
+/// This is passed to both the `reconciler` and the `error_policy`, so that they can (for example) take a
+/// cheaper path for periodic requeues. Keep in mind that several requests for the same object are merged
+/// before the reconciler runs, in which case only the reason of the earliest-scheduled request is kept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReconcileReason {
+    /// The reason is not known, for example because the request came from a bare [`ObjectRef`]
+    Unknown,
+    /// The object itself was created or modified
+    ObjectUpdated,
+    /// A related object (a child registered with [`Controller::owns_with`], or an object mapped by
+    /// [`Controller::watches_with`]) was modified or deleted
+    RelatedObjectUpdated {
+        /// The object that triggered the reconciliation
+        obj_ref: Box<ObjectRef<DynamicObject>>,
+    },
+    /// The reconciler asked to be requeued using [`ReconcilerAction::requeue_after`]
+    ReconcilerRequestedRetry,
+    /// The `error_policy` asked to be requeued after a failed reconciliation
+    ErrorPolicyRequestedRetry,
+    /// All objects were enqueued, for example by [`Controller::resync_period`] or [`Controller::reconcile_all_on`]
+    BulkReconcile,
+    /// A user-defined reason, for requests sent through [`Controller::reconcile_on`]
+    Custom {
+        /// The user-defined reason
+        reason: String,
+    },
+}
+
+impl Display for ReconcileReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReconcileReason::Unknown => f.write_str("unknown"),
+            ReconcileReason::ObjectUpdated => f.write_str("object updated"),
+            ReconcileReason::RelatedObjectUpdated { obj_ref } => {
+                write!(f, "related object updated: {}", obj_ref)
+            }
+            ReconcileReason::ReconcilerRequestedRetry => f.write_str("reconciler requested retry"),
+            ReconcileReason::ErrorPolicyRequestedRetry => f.write_str("error policy requested retry"),
+            ReconcileReason::BulkReconcile => f.write_str("bulk reconcile requested"),
+            ReconcileReason::Custom { reason } => f.write_str(reason),
+        }
+    }
+}
+
+/// A request to reconcile the object referred to by `obj_ref`, for a given `reason`
+///
+/// Requests are considered equal (and are deduplicated by the scheduler) if they refer to the same object,
+/// regardless of their reason.
+#[derive(Derivative)]
+#[derivative(
+    Debug(bound = "K::DynamicType: Debug"),
+    Clone(bound = "K::DynamicType: Clone"),
+    PartialEq(bound = "K::DynamicType: PartialEq"),
+    Eq(bound = "K::DynamicType: Eq"),
+    Hash(bound = "K::DynamicType: Hash")
+)]
+pub struct ReconcileRequest<K: Resource> {
+    /// The object to reconcile
+    pub obj_ref: ObjectRef<K>,
+    /// Why the reconciliation was requested
+    #[derivative(PartialEq = "ignore", Hash = "ignore")]
+    pub reason: ReconcileReason,
+}
+
+impl<K: Resource> From<ObjectRef<K>> for ReconcileRequest<K> {
+    fn from(obj_ref: ObjectRef<K>) -> Self {
+        ReconcileRequest {
+            obj_ref,
+            reason: ReconcileReason::Unknown,
+        }
+    }
+}
+
 /// Helper for building custom trigger filters, see the implementations of [`trigger_self`] and [`trigger_owners`] for some examples.
+///
+/// The `mapper` may return either bare [`ObjectRef`]s (which will be enqueued with [`ReconcileReason::Unknown`]),
+/// or full [`ReconcileRequest`]s.
 pub fn trigger_with<T, K, I, S>(
     stream: S,
     mapper: impl Fn(T) -> I,
-) -> impl Stream<Item = Result<ObjectRef<K>, S::Error>>
+) -> impl Stream<Item = Result<ReconcileRequest<K>, S::Error>>
 where
     S: TryStream<Ok = T>,
-    I: IntoIterator<Item = ObjectRef<K>>,
+    I: IntoIterator,
+    I::Item: Into<ReconcileRequest<K>>,
     K: Resource,
 {
     stream
-        .map_ok(move |obj| stream::iter(mapper(obj).into_iter().map(Ok)))
+        .map_ok(move |obj| stream::iter(mapper(obj).into_iter().map(|req| Ok(req.into()))))
         .try_flatten()
 }
 
@@ -76,14 +174,17 @@ where
 pub fn trigger_self<K, S>(
     stream: S,
     dyntype: K::DynamicType,
-) -> impl Stream<Item = Result<ObjectRef<K>, S::Error>>
+) -> impl Stream<Item = Result<ReconcileRequest<K>, S::Error>>
 where
     S: TryStream<Ok = K>,
     K: Resource,
     K::DynamicType: Clone,
 {
     trigger_with(stream, move |obj| {
-        Some(ObjectRef::from_obj_with(&obj, dyntype.clone()))
+        Some(ReconcileRequest {
+            obj_ref: ObjectRef::from_obj_with(&obj, dyntype.clone()),
+            reason: ReconcileReason::ObjectUpdated,
+        })
     })
 }
 
@@ -91,21 +192,168 @@ where
 pub fn trigger_owners<KOwner, S>(
     stream: S,
     owner_type: KOwner::DynamicType,
-) -> impl Stream<Item = Result<ObjectRef<KOwner>, S::Error>>
+    child_type: <S::Ok as Resource>::DynamicType,
+) -> impl Stream<Item = Result<ReconcileRequest<KOwner>, S::Error>>
 where
     S: TryStream,
     S::Ok: Resource,
+    <S::Ok as Resource>::DynamicType: Clone,
     KOwner: Resource,
     KOwner::DynamicType: Clone,
 {
     trigger_with(stream, move |obj| {
-        let meta = obj.meta().clone();
-        let ns = meta.namespace;
-        let dt = owner_type.clone();
-        meta.owner_references
-            .into_iter()
-            .flatten()
-            .flat_map(move |owner| ObjectRef::from_owner_ref(ns.as_deref(), &owner, dt.clone()))
+        let child_ref = ObjectRef::from_obj_with(&obj, child_type.clone()).erase();
+        owners_of(&obj, owner_type.clone()).map(move |obj_ref| ReconcileRequest {
+            obj_ref,
+            reason: ReconcileReason::RelatedObjectUpdated {
+                obj_ref: Box::new(child_ref.clone()),
+            },
+        })
+    })
+}
+
+/// References to the owners of type `KOwner` of `obj`
+fn owners_of<KOwner>(
+    obj: &impl Resource,
+    owner_type: KOwner::DynamicType,
+) -> impl Iterator<Item = ObjectRef<KOwner>>
+where
+    KOwner: Resource,
+    KOwner::DynamicType: Clone,
+{
+    let meta = obj.meta().clone();
+    let ns = meta.namespace;
+    meta.owner_references
+        .into_iter()
+        .flatten()
+        .filter_map(move |owner| ObjectRef::from_owner_ref(ns.as_deref(), &owner, owner_type.clone()))
+}
+
+/// A reconciliation that is currently in progress, see [`QueueInspector`]
+#[derive(Derivative)]
+#[derivative(
+    Debug(bound = "K::DynamicType: Debug"),
+    Clone(bound = "K::DynamicType: Clone")
+)]
+pub struct RunningReconcile<K: Resource> {
+    /// The request that is being reconciled
+    pub request: ReconcileRequest<K>,
+    /// When the reconciler was started
+    pub started_at: Instant,
+}
+
+/// A point-in-time view of the reconciliation queue of a [`Controller`] or [`applier`]
+#[derive(Derivative)]
+#[derivative(
+    Debug(bound = "K::DynamicType: Debug"),
+    Clone(bound = "K::DynamicType: Clone")
+)]
+pub struct QueueSnapshot<K: Resource> {
+    /// Requests that are waiting for their `run_at` time, ordered by `run_at`
+    pub scheduled: Vec<ScheduleRequest<ReconcileRequest<K>>>,
+    /// Requests that are due, but are held until a running reconciliation of the same object has finished
+    pub pending: Vec<ReconcileRequest<K>>,
+    /// Reconciliations that are currently running, ordered by when they were started
+    pub running: Vec<RunningReconcile<K>>,
+}
+
+/// A read-only handle for inspecting the reconciliation queue of a [`Controller`] or [`applier`]
+///
+/// This is intended for debugging operators that appear to be stuck, for example by rendering
+/// [`QueueInspector::snapshot`] on a debug HTTP endpoint.
+///
+/// Inspectors are handed out by [`Controller::inspector`] and [`applier_with_inspector`].
+/// Cloning will produce a new reference to the same queue.
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""))]
+pub struct QueueInspector<K: Resource> {
+    scheduler: SchedulerInspector<ReconcileRequest<K>>,
+    running: RunningMessages<ReconcileRequest<K>>,
+}
+
+impl<K: Resource> QueueInspector<K> {
+    /// Creates the state of a new queue, which must only be passed to a single applier
+    fn new() -> Self {
+        Self {
+            scheduler: SchedulerInspector::new(),
+            running: RunningMessages::default(),
+        }
+    }
+}
+
+impl<K: Resource> QueueInspector<K>
+where
+    K::DynamicType: Clone,
+{
+    /// Takes a snapshot of all scheduled, pending, and running reconciliations
+    ///
+    /// The scheduled/pending and running parts are read separately, so a request that is in the middle of being
+    /// started may be missing from the snapshot.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the queue's locks have been poisoned by a panic while they were held.
+    #[must_use]
+    pub fn snapshot(&self) -> QueueSnapshot<K> {
+        let mut running = self
+            .running
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(request, started_at)| RunningReconcile {
+                request: request.clone(),
+                started_at: *started_at,
+            })
+            .collect::<Vec<_>>();
+        running.sort_by_key(|reconcile| reconcile.started_at);
+        QueueSnapshot {
+            scheduled: self.scheduler.scheduled(),
+            pending: self.scheduler.pending(),
+            running,
+        }
+    }
+}
+
+/// Periodically enqueues every object in `store` for reconciliation
+///
+/// The first resync happens `period` after the stream is first polled. Each object is delayed by a
+/// stable pseudo-random offset within the `period`, so that a large store is spread out over the whole
+/// period rather than being enqueued in a single burst.
+pub fn trigger_resync<K>(
+    store: Store<K>,
+    dyntype: K::DynamicType,
+    period: Duration,
+) -> impl Stream<Item = ReconcileRequest<K>>
+where
+    K: Clone + Resource + 'static,
+    K::DynamicType: Eq + Hash + Clone,
+{
+    stream::unfold(
+        time::interval_at(Instant::now() + period, period),
+        |mut interval| async move { Some((interval.tick().await, interval)) },
+    )
+    .flat_map(move |tick| {
+        let mut obj_refs = store
+            .state()
+            .iter()
+            .map(|obj| {
+                let obj_ref = ObjectRef::from_obj_with(obj, dyntype.clone());
+                // Not randomized, so that each object keeps the same offset for every resync
+                let mut hasher = DefaultHasher::new();
+                obj_ref.hash(&mut hasher);
+                let offset_nanos = u128::from(hasher.finish()) % period.as_nanos().max(1);
+                let offset = Duration::from_nanos(u64::try_from(offset_nanos).unwrap_or(u64::MAX));
+                (offset, obj_ref)
+            })
+            .collect::<Vec<_>>();
+        obj_refs.sort_by_key(|(offset, _)| *offset);
+        stream::iter(obj_refs).then(move |(offset, obj_ref)| async move {
+            time::sleep_until(tick + offset).await;
+            ReconcileRequest {
+                obj_ref,
+                reason: ReconcileReason::BulkReconcile,
+            }
+        })
     })
 }
 
@@ -150,29 +398,88 @@ impl<T> Context<T> {
 /// This is the "hard-mode" version of [`Controller`], which allows you some more customization
 /// (such as triggering from arbitrary [`Stream`]s), at the cost of being a bit more verbose.
 pub fn applier<K, QueueStream, ReconcilerFut, T>(
-    mut reconciler: impl FnMut(K, Context<T>) -> ReconcilerFut,
-    mut error_policy: impl FnMut(&ReconcilerFut::Error, Context<T>) -> ReconcilerAction,
+    reconciler: impl FnMut(K, ReconcileReason, Context<T>) -> ReconcilerFut,
+    error_policy: impl FnMut(&ReconcilerFut::Error, &ReconcileReason, Context<T>) -> ReconcilerAction,
+    context: Context<T>,
+    store: Store<K>,
+    queue: QueueStream,
+) -> impl Stream<Item = Result<(ObjectRef<K>, ReconcilerAction), Error<ReconcilerFut::Error, QueueStream::Error>>>
+where
+    K: Clone + Resource + 'static,
+    K::DynamicType: Debug + Eq + Hash + Clone + Unpin,
+    ReconcilerFut: TryFuture<Ok = ReconcilerAction> + Unpin,
+    ReconcilerFut::Error: std::error::Error + 'static,
+    QueueStream: TryStream,
+    QueueStream::Ok: Into<ReconcileRequest<K>>,
+    QueueStream::Error: std::error::Error + 'static,
+{
+    applier_with_inspector(reconciler, error_policy, context, store, queue).1
+}
+
+/// Variant of [`applier`] that also returns a [`QueueInspector`] for inspecting its queue
+#[allow(clippy::type_complexity)]
+pub fn applier_with_inspector<K, QueueStream, ReconcilerFut, T>(
+    mut reconciler: impl FnMut(K, ReconcileReason, Context<T>) -> ReconcilerFut,
+    error_policy: impl FnMut(&ReconcilerFut::Error, &ReconcileReason, Context<T>) -> ReconcilerAction,
     context: Context<T>,
     store: Store<K>,
     queue: QueueStream,
+) -> (
+    QueueInspector<K>,
+    impl Stream<Item = Result<(ObjectRef<K>, ReconcilerAction), Error<ReconcilerFut::Error, QueueStream::Error>>>,
+)
+where
+    K: Clone + Resource + 'static,
+    K::DynamicType: Debug + Eq + Hash + Clone + Unpin,
+    ReconcilerFut: TryFuture<Ok = ReconcilerAction> + Unpin,
+    ReconcilerFut::Error: std::error::Error + 'static,
+    QueueStream: TryStream,
+    QueueStream::Ok: Into<ReconcileRequest<K>>,
+    QueueStream::Error: std::error::Error + 'static,
+{
+    let inspector = QueueInspector::new();
+    let applier = applier_with_refs(
+        move |_obj_ref, obj, reason, ctx| reconciler(obj, reason, ctx),
+        error_policy,
+        context,
+        store,
+        queue,
+        inspector.clone(),
+    );
+    (inspector, applier)
+}
+
+/// Variant of [`applier`] that also passes the reconciler the [`ObjectRef`] that was requested
+///
+/// This lets the reconciler see properties of the reference that are not part of the object, such as [`ObjectRef::cluster`].
+/// The queue state is kept in `inspector`, which must have been created by `QueueInspector::new` for this applier alone.
+pub(crate) fn applier_with_refs<K, QueueStream, ReconcilerFut, T>(
+    mut reconciler: impl FnMut(&ObjectRef<K>, K, ReconcileReason, Context<T>) -> ReconcilerFut,
+    mut error_policy: impl FnMut(&ReconcilerFut::Error, &ReconcileReason, Context<T>) -> ReconcilerAction,
+    context: Context<T>,
+    store: Store<K>,
+    queue: QueueStream,
+    inspector: QueueInspector<K>,
 ) -> impl Stream<Item = Result<(ObjectRef<K>, ReconcilerAction), Error<ReconcilerFut::Error, QueueStream::Error>>>
 where
     K: Clone + Resource + 'static,
     K::DynamicType: Debug + Eq + Hash + Clone + Unpin,
     ReconcilerFut: TryFuture<Ok = ReconcilerAction> + Unpin,
     ReconcilerFut::Error: std::error::Error + 'static,
-    QueueStream: TryStream<Ok = ObjectRef<K>>,
+    QueueStream: TryStream,
+    QueueStream::Ok: Into<ReconcileRequest<K>>,
     QueueStream::Error: std::error::Error + 'static,
 {
+    let QueueInspector { scheduler, running } = inspector;
     let err_context = context.clone();
-    let (scheduler_tx, scheduler_rx) = channel::mpsc::channel::<ScheduleRequest<ObjectRef<K>>>(100);
+    let (scheduler_tx, scheduler_rx) = channel::mpsc::channel::<ScheduleRequest<ReconcileRequest<K>>>(100);
     // Create a stream of ObjectRefs that need to be reconciled
     trystream_try_via(
         // input: stream combining scheduled tasks and user specified inputs event
         Box::pin(stream::select(
             // 1. inputs from users queue stream
-            queue.context(QueueError).map_ok(|obj_ref| ScheduleRequest {
-                message: obj_ref,
+            queue.context(QueueError).map_ok(|request| ScheduleRequest {
+                message: request.into(),
                 run_at: Instant::now() + Duration::from_millis(1),
             }),
             // 2. requests sent to scheduler_tx
@@ -180,33 +487,39 @@ where
         )),
         // all the Oks from the select gets passed through the scheduler stream, and are then executed
         move |s| {
-            Runner::new(scheduler(s), move |obj_ref| {
-                let obj_ref = obj_ref.clone();
-                match store.get(&obj_ref) {
-                    Some(obj) => reconciler(obj, context.clone())
+            Runner::new(scheduler_with_inspector(s, scheduler), move |request| {
+                let request = request.clone();
+                match store.get(&request.obj_ref) {
+                    Some(obj) => reconciler(&request.obj_ref, obj, request.reason.clone(), context.clone())
                         .into_future()
                         // Reconciler errors are OK from the applier's PoV, we need to apply the error policy
                         // to them separately
-                        .map(|res| Ok((obj_ref, res)))
+                        .map(|res| Ok((request, res)))
                         .left_future(),
                     None => future::err(
                         ObjectNotFound {
-                            obj_ref: obj_ref.erase(),
+                            obj_ref: request.obj_ref.erase(),
                         }
                         .build(),
                     )
                     .right_future(),
                 }
             })
+            .with_running(running)
             .context(SchedulerDequeueFailed)
             .map(|res| res.and_then(|x| x))
         },
     )
     // finally, for each completed reconcile call:
-    .and_then(move |(obj_ref, reconciler_result)| {
-        let ReconcilerAction { requeue_after } = match &reconciler_result {
-            Ok(action) => action.clone(),                       // do what user told us
-            Err(err) => error_policy(err, err_context.clone()), // reconciler fn call failed
+    .and_then(move |(ReconcileRequest { obj_ref, reason }, reconciler_result)| {
+        let (ReconcilerAction { requeue_after }, requeue_reason) = match &reconciler_result {
+            // do what user told us
+            Ok(action) => (action.clone(), ReconcileReason::ReconcilerRequestedRetry),
+            // reconciler fn call failed
+            Err(err) => (
+                error_policy(err, &reason, err_context.clone()),
+                ReconcileReason::ErrorPolicyRequestedRetry,
+            ),
         };
         let mut scheduler_tx = scheduler_tx.clone();
         async move {
@@ -214,7 +527,10 @@ where
             if let Some(delay) = requeue_after {
                 scheduler_tx
                     .send(ScheduleRequest {
-                        message: obj_ref.clone(),
+                        message: ReconcileRequest {
+                            obj_ref: obj_ref.clone(),
+                            reason: requeue_reason,
+                        },
                         run_at: Instant::now() + delay,
                     })
                     .await
@@ -244,7 +560,7 @@ where
 /// use serde::{Deserialize, Serialize};
 /// use tokio::time::Duration;
 /// use futures::StreamExt;
-/// use kube_runtime::controller::{Context, Controller, ReconcilerAction};
+/// use kube_runtime::controller::{Context, Controller, ReconcileReason, ReconcilerAction};
 /// use k8s_openapi::api::core::v1::ConfigMap;
 /// use schemars::JsonSchema;
 ///
@@ -259,7 +575,7 @@ where
 /// }
 ///
 /// /// The reconciler that will be called when either object change
-/// async fn reconcile(g: ConfigMapGenerator, _ctx: Context<()>) -> Result<ReconcilerAction, Error> {
+/// async fn reconcile(g: ConfigMapGenerator, _reason: ReconcileReason, _ctx: Context<()>) -> Result<ReconcilerAction, Error> {
 ///     // .. use api here to reconcile a child ConfigMap with ownerreferences
 ///     // see configmapgen_controller example for full info
 ///     Ok(ReconcilerAction {
@@ -267,7 +583,7 @@ where
 ///     })
 /// }
 /// /// an error handler that will be called when the reconciler fails
-/// fn error_policy(_error: &Error, _ctx: Context<()>) -> ReconcilerAction {
+/// fn error_policy(_error: &Error, _reason: &ReconcileReason, _ctx: Context<()>) -> ReconcilerAction {
 ///     ReconcilerAction {
 ///         requeue_after: Some(Duration::from_secs(60)),
 ///     }
@@ -300,11 +616,16 @@ where
 {
     // NB: Need to Unpin for stream::select_all
     // TODO: get an arbitrary std::error::Error in here?
-    selector: SelectAll<BoxStream<'static, Result<ObjectRef<K>, watcher::Error>>>,
+    selector: SelectAll<BoxStream<'static, Result<ReconcileRequest<K>, watcher::Error>>>,
     dyntype: K::DynamicType,
     reader: Store<K>,
+    inspector: QueueInspector<K>,
+    indexers: Indexers<K>,
 }
 
+/// Callbacks that keep secondary indexes (such as [`ReferenceIndex`]) up to date with the [`Controller`]'s own objects
+type Indexers<K> = Arc<Mutex<Vec<Box<dyn Fn(&watcher::Event<K>) + Send>>>>;
+
 impl<K> Controller<K>
 where
     K: Clone + Resource + DeserializeOwned + Debug + Send + Sync + 'static,
@@ -332,12 +653,24 @@ where
     ///
     /// Unlike `new`, this function accepts `K::DynamicType` so it can be used with dynamic
     /// resources.
+    ///
+    /// # Panics
+    ///
+    /// The returned `Controller` panics if an extractor passed to [`Controller::watches_references`] panics.
     pub fn new_with(owned_api: Api<K>, lp: ListParams, dyntype: K::DynamicType) -> Self {
         let writer = Writer::<K>::new(dyntype.clone());
         let reader = writer.as_reader();
         let mut selector = stream::SelectAll::new();
+        let indexers = Indexers::<K>::default();
+        let self_indexers = indexers.clone();
         let self_watcher = trigger_self(
-            try_flatten_applied(reflector(writer, watcher(owned_api, lp))),
+            try_flatten_applied(
+                reflector(writer, watcher(owned_api, lp)).inspect_ok(move |event| {
+                    for indexer in self_indexers.lock().unwrap().iter() {
+                        indexer(event);
+                    }
+                }),
+            ),
             dyntype.clone(),
         )
         .boxed();
@@ -346,6 +679,8 @@ where
             selector,
             reader,
             dyntype,
+            inspector: QueueInspector::new(),
+            indexers,
         }
     }
 
@@ -354,6 +689,14 @@ where
         self.reader.clone()
     }
 
+    /// Retrieve a handle for inspecting the reconciliation queue before starting the controller
+    ///
+    /// See [`QueueInspector`] for more details.
+    #[must_use]
+    pub fn inspector(&self) -> QueueInspector<K> {
+        self.inspector.clone()
+    }
+
     /// Indicate child objets `K` owns and be notified when they change
     ///
     /// This type `Child` must have [`OwnerReference`] set to point back to `K`.
@@ -361,6 +704,9 @@ where
     /// only a subset of `Child` entries are required.
     /// The `api` must have the correct scope (cluster/all namespaces, or namespaced)
     ///
+    /// The owners are reconciled with [`ReconcileReason::Unknown`], since the type of `Child` is not known
+    /// at runtime. Use [`Controller::owns_with`] to be told which child changed.
+    ///
     /// [`OwnerReference`]: https://docs.rs/k8s-openapi/0.10.0/k8s_openapi/apimachinery/pkg/apis/meta/v1/struct.OwnerReference.html
     pub fn owns<Child: Clone + Resource + DeserializeOwned + Debug + Send + 'static>(
         mut self,
@@ -370,14 +716,41 @@ where
     where
         Child::DynamicType: Debug + Eq + Hash,
     {
-        let child_watcher = trigger_owners(try_flatten_touched(watcher(api, lp)), self.dyntype.clone());
+        let owner_type = self.dyntype.clone();
+        let child_watcher = trigger_with(try_flatten_touched(watcher(api, lp)), move |obj| {
+            owners_of(&obj, owner_type.clone())
+        });
+        self.selector.push(child_watcher.boxed());
+        self
+    }
+
+    /// Indicate child objets `K` owns and be notified when they change
+    ///
+    /// Unlike `owns`, this function accepts `Child::DynamicType`, so that the owners are reconciled with a
+    /// [`ReconcileReason::RelatedObjectUpdated`] that refers to the child that changed.
+    #[must_use]
+    pub fn owns_with<Child: Clone + Resource + DeserializeOwned + Debug + Send + 'static>(
+        mut self,
+        api: Api<Child>,
+        dyntype: Child::DynamicType,
+        lp: ListParams,
+    ) -> Self
+    where
+        Child::DynamicType: Debug + Eq + Hash + Clone,
+    {
+        let child_watcher = trigger_owners(
+            try_flatten_touched(watcher(api, lp)),
+            self.dyntype.clone(),
+            dyntype,
+        );
         self.selector.push(child_watcher.boxed());
         self
     }
 
     /// Indicate an object to watch with a custom mapper
     ///
-    /// This mapper should return something like `Option<ObjectRef<K>>`
+    /// This mapper should return something like `Option<ObjectRef<K>>`. The mapped objects are reconciled
+    /// with [`ReconcileReason::Unknown`], use [`Controller::watches_with`] to be told which object changed.
     pub fn watches<
         Other: Clone + Resource + DeserializeOwned + Debug + Send + 'static,
         I: 'static + IntoIterator<Item = ObjectRef<K>>,
@@ -395,15 +768,222 @@ where
         self
     }
 
+    /// Indicate an object to watch with a custom mapper
+    ///
+    /// Unlike `watches`, this function accepts `Other::DynamicType`, so that the mapped objects are reconciled with
+    /// a [`ReconcileReason::RelatedObjectUpdated`] that refers to the object that changed.
+    #[must_use]
+    pub fn watches_with<
+        Other: Clone + Resource + DeserializeOwned + Debug + Send + 'static,
+        I: 'static + IntoIterator<Item = ObjectRef<K>>,
+    >(
+        mut self,
+        api: Api<Other>,
+        dyntype: Other::DynamicType,
+        lp: ListParams,
+        mapper: impl Fn(Other) -> I + Send + 'static,
+    ) -> Self
+    where
+        I::IntoIter: Send,
+        Other::DynamicType: Clone,
+    {
+        let other_watcher = trigger_with(try_flatten_touched(watcher(api, lp)), move |obj| {
+            let other_ref = ObjectRef::from_obj_with(&obj, dyntype.clone()).erase();
+            mapper(obj).into_iter().map(move |obj_ref| ReconcileRequest {
+                obj_ref,
+                reason: ReconcileReason::RelatedObjectUpdated {
+                    obj_ref: Box::new(other_ref.clone()),
+                },
+            })
+        });
+        self.selector.push(other_watcher.boxed());
+        self
+    }
+
+    /// Indicate objects that `K` refers to, and reconcile the referring objects when they change
+    ///
+    /// The `extractor` should return every `Ref` that a given `K` refers to (for example, a `ConfigMap` named in
+    /// its `spec`). The `Controller` keeps a [`ReferenceIndex`] of these references up to date, so that a change to
+    /// a `Ref` enqueues exactly the objects that refer to it, without scanning the whole [`Store`].
+    ///
+    /// ```no_run
+    /// # async fn doc(client: kube::Client) {
+    /// use k8s_openapi::api::{apps::v1::Deployment, core::v1::Secret};
+    /// use kube::api::{Api, ListParams, ResourceExt};
+    /// use kube_runtime::{reflector::ObjectRef, Controller};
+    ///
+    /// let controller = Controller::new(Api::<Deployment>::all(client.clone()), ListParams::default())
+    ///     .watches_references(Api::<Secret>::all(client), ListParams::default(), |deploy| {
+    ///         let ns = deploy.namespace().unwrap_or_default();
+    ///         deploy.spec.iter()
+    ///             .filter_map(|spec| spec.template.spec.as_ref())
+    ///             .flat_map(|pod_spec| pod_spec.image_pull_secrets.iter().flatten())
+    ///             .filter_map(|secret| secret.name.as_deref())
+    ///             .map(|name| ObjectRef::new(name).within(&ns))
+    ///             .collect::<Vec<_>>()
+    ///     });
+    /// # }
+    /// ```
+    ///
+    /// Keep in mind that `Ref`s that change before their referrers have been seen by the `Controller` will not
+    /// trigger anything, but every `K` is reconciled when it is first seen anyway.
+    #[must_use]
+    pub fn watches_references<Ref, I>(
+        self,
+        api: Api<Ref>,
+        lp: ListParams,
+        extractor: impl Fn(&K) -> I + Send + Sync + 'static,
+    ) -> Self
+    where
+        Ref: Clone + Resource + DeserializeOwned + Debug + Send + 'static,
+        Ref::DynamicType: Debug + Eq + Hash + Clone + Default,
+        I: IntoIterator<Item = ObjectRef<Ref>>,
+    {
+        self.watches_references_with(api, Default::default(), lp, extractor)
+    }
+
+    /// Indicate objects that `K` refers to, and reconcile the referring objects when they change
+    ///
+    /// Unlike `watches_references`, this function accepts `Ref::DynamicType` so it can be used with dynamic
+    /// resources.
+    ///
+    /// # Panics
+    ///
+    /// Panics if another extractor of this `Controller` has panicked.
+    #[must_use]
+    pub fn watches_references_with<Ref, I>(
+        mut self,
+        api: Api<Ref>,
+        dyntype: Ref::DynamicType,
+        lp: ListParams,
+        extractor: impl Fn(&K) -> I + Send + Sync + 'static,
+    ) -> Self
+    where
+        Ref: Clone + Resource + DeserializeOwned + Debug + Send + 'static,
+        Ref::DynamicType: Debug + Eq + Hash + Clone,
+        I: IntoIterator<Item = ObjectRef<Ref>>,
+    {
+        let index = ReferenceIndex::new(self.dyntype.clone(), extractor);
+        let self_index = index.clone();
+        self.indexers
+            .lock()
+            .unwrap()
+            .push(Box::new(move |event| self_index.apply_watcher_event(event)));
+        let ref_watcher = trigger_with(try_flatten_touched(watcher(api, lp)), move |obj| {
+            let ref_ref = ObjectRef::from_obj_with(&obj, dyntype.clone());
+            let referrers = index.referrers(&ref_ref);
+            let ref_ref = ref_ref.erase();
+            referrers.into_iter().map(move |obj_ref| ReconcileRequest {
+                obj_ref,
+                reason: ReconcileReason::RelatedObjectUpdated {
+                    obj_ref: Box::new(ref_ref.clone()),
+                },
+            })
+        });
+        self.selector.push(ref_watcher.boxed());
+        self
+    }
+
+    /// Trigger a reconciliation for every `ObjectRef` (or [`ReconcileRequest`]) emitted by an external stream
+    ///
+    /// This can be used to reconcile objects in response to events from outside of Kubernetes,
+    /// such as webhooks or messages from an external queue. The objects are scheduled like any
+    /// other trigger, so they are deduplicated against already-queued reconciliations.
+    ///
+    /// Bare `ObjectRef`s are reconciled with [`ReconcileReason::Unknown`], send a [`ReconcileRequest`]
+    /// with a [`ReconcileReason::Custom`] reason to tell the reconciler where the request came from.
+    ///
+    /// To get a cloneable handle that can be used from anywhere in the process, pass in the
+    /// receiving end of a channel:
+    ///
+    /// ```no_run
+    /// # async fn doc(client: kube::Client) {
+    /// use futures::{channel::mpsc, SinkExt};
+    /// use k8s_openapi::api::core::v1::ConfigMap;
+    /// use kube::api::{Api, ListParams};
+    /// use kube_runtime::{reflector::ObjectRef, Controller};
+    ///
+    /// let (mut trigger_tx, trigger_rx) = mpsc::channel::<ObjectRef<ConfigMap>>(16);
+    /// let controller = Controller::new(Api::<ConfigMap>::all(client), ListParams::default())
+    ///     .reconcile_on(trigger_rx);
+    /// // ...later, from a webhook handler
+    /// trigger_tx.send(ObjectRef::new("my-cm").within("default")).await.unwrap();
+    /// # }
+    /// ```
+    ///
+    /// The object must still exist in the controller's [`Store`] when the reconciliation is started,
+    /// otherwise it will fail with an [`Error::ObjectNotFound`].
+    #[must_use]
+    pub fn reconcile_on<R>(mut self, trigger: impl Stream<Item = R> + Send + 'static) -> Self
+    where
+        R: Into<ReconcileRequest<K>>,
+    {
+        self.selector
+            .push(trigger.map(|request| Ok(request.into())).boxed());
+        self
+    }
+
+    /// Trigger a reconciliation for all currently known objects every time `trigger` emits an item
+    ///
+    /// This is useful for reacting to changes in shared external state that any object might depend on.
+    /// The objects are taken from the controller's [`Store`] at the time that the trigger is received.
+    ///
+    /// ```no_run
+    /// # async fn doc(client: kube::Client) {
+    /// use futures::channel::mpsc;
+    /// use k8s_openapi::api::core::v1::ConfigMap;
+    /// use kube::api::{Api, ListParams};
+    /// use kube_runtime::Controller;
+    ///
+    /// let (reconcile_all_tx, reconcile_all_rx) = mpsc::channel::<()>(1);
+    /// let controller = Controller::new(Api::<ConfigMap>::all(client), ListParams::default())
+    ///     .reconcile_all_on(reconcile_all_rx);
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn reconcile_all_on(mut self, trigger: impl Stream<Item = ()> + Send + 'static) -> Self {
+        let store = self.store();
+        let dyntype = self.dyntype.clone();
+        self.selector.push(
+            trigger
+                .flat_map(move |()| {
+                    let dyntype = dyntype.clone();
+                    stream::iter(store.state().into_iter().map(move |obj| {
+                        Ok(ReconcileRequest {
+                            obj_ref: ObjectRef::from_obj_with(&obj, dyntype.clone()),
+                            reason: ReconcileReason::BulkReconcile,
+                        })
+                    }))
+                })
+                .boxed(),
+        );
+        self
+    }
+
+    /// Reconcile every object in the [`Store`] periodically, even if nothing has changed
+    ///
+    /// This is a safety net for drift in external systems that the controller has no way to watch.
+    /// The resyncs are spread out over the whole `period` (see [`trigger_resync`]), and are deduplicated
+    /// against any reconciliations that are already scheduled.
+    ///
+    /// To resync individual objects at different intervals, return [`ReconcilerAction::requeue_after`]
+    /// from the reconciler instead.
+    #[must_use]
+    pub fn resync_period(mut self, period: Duration) -> Self {
+        let resync = trigger_resync(self.store(), self.dyntype.clone(), period);
+        self.selector.push(resync.map(Ok).boxed());
+        self
+    }
+
     /// Consume all the parameters of the Controller and start the applier stream
     ///
     /// This creates a stream from all builder calls and starts an applier with
     /// a specified `reconciler` and `error_policy` callbacks. Each of these will be called
-    /// with a configurable [`Context`].
+    /// with the [`ReconcileReason`] for the reconciliation, and a configurable [`Context`].
     pub fn run<ReconcilerFut, T>(
         self,
-        mut reconciler: impl FnMut(K, Context<T>) -> ReconcilerFut,
-        error_policy: impl FnMut(&ReconcilerFut::Error, Context<T>) -> ReconcilerAction,
+        mut reconciler: impl FnMut(K, ReconcileReason, Context<T>) -> ReconcilerFut,
+        error_policy: impl FnMut(&ReconcilerFut::Error, &ReconcileReason, Context<T>) -> ReconcilerAction,
         context: Context<T>,
     ) -> impl Stream<Item = Result<(ObjectRef<K>, ReconcilerAction), Error<ReconcilerFut::Error, watcher::Error>>>
     where
@@ -411,24 +991,36 @@ where
         ReconcilerFut: TryFuture<Ok = ReconcilerAction> + Send + 'static,
         ReconcilerFut::Error: std::error::Error + Send + 'static,
     {
-        applier(
-            move |obj, ctx| {
-                CancelableJoinHandle::spawn(reconciler(obj, ctx).into_future(), &Handle::current())
+        applier_with_refs(
+            move |_obj_ref, obj, reason, ctx| {
+                CancelableJoinHandle::spawn(reconciler(obj, reason, ctx).into_future(), &Handle::current())
             },
             error_policy,
             context,
             self.reader,
             self.selector,
+            self.inspector,
         )
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Context, ReconcilerAction};
-    use crate::Controller;
+    use super::{trigger_resync, Context, ReconcileReason, ReconcilerAction};
+    use crate::{
+        reflector::{store::Writer, ObjectRef},
+        watcher, Controller,
+    };
+    use futures::{poll, StreamExt};
     use k8s_openapi::api::core::v1::ConfigMap;
-    use kube::Api;
+    use kube::{
+        api::{ListParams, ObjectMeta, Patch, PatchParams},
+        fake::FakeApiServer,
+        Api,
+    };
+    use serde_json::json;
+    use std::{collections::HashSet, convert::Infallible};
+    use tokio::time::{advance, pause, Duration};
 
     fn assert_send<T: Send>(x: T) -> T {
         x
@@ -445,10 +1037,74 @@ mod tests {
     fn test_controller_should_be_send() {
         assert_send(
             Controller::new(mock_type::<Api<ConfigMap>>(), Default::default()).run(
-                |_, _| async { Ok(mock_type::<ReconcilerAction>()) },
-                |_: &std::io::Error, _| mock_type::<ReconcilerAction>(),
+                |_, _, _| async { Ok(mock_type::<ReconcilerAction>()) },
+                |_: &std::io::Error, _, _| mock_type::<ReconcilerAction>(),
                 Context::new(()),
             ),
         );
     }
+
+    #[tokio::test]
+    async fn controller_should_reconcile_objects_from_fake_apiserver() {
+        let server = FakeApiServer::new();
+        server.insert(&ConfigMap {
+            metadata: ObjectMeta {
+                name: Some("existing".to_string()),
+                namespace: Some("ns".to_string()),
+                ..ObjectMeta::default()
+            },
+            ..ConfigMap::default()
+        });
+        let cms = Api::<ConfigMap>::namespaced(server.client(), "ns");
+        let mut reconciled = Box::pin(Controller::new(cms.clone(), ListParams::default()).run(
+            |_, _, _| async { Ok::<_, Infallible>(ReconcilerAction { requeue_after: None }) },
+            |_, _, _| ReconcilerAction { requeue_after: None },
+            Context::new(()),
+        ));
+        let (obj_ref, _) = reconciled.next().await.unwrap().unwrap();
+        assert_eq!(obj_ref.name, "existing");
+
+        cms.patch(
+            "created",
+            &PatchParams::apply("test"),
+            &Patch::Apply(json!({ "apiVersion": "v1", "kind": "ConfigMap" })),
+        )
+        .await
+        .unwrap();
+        let (obj_ref, _) = reconciled.next().await.unwrap().unwrap();
+        assert_eq!(obj_ref.name, "created");
+    }
+
+    #[tokio::test]
+    async fn resync_should_enqueue_all_objects_once_per_period() {
+        pause();
+        let cms = (0..10)
+            .map(|i| ConfigMap {
+                metadata: ObjectMeta {
+                    name: Some(format!("cm-{}", i)),
+                    namespace: Some("ns".to_string()),
+                    ..ObjectMeta::default()
+                },
+                ..ConfigMap::default()
+            })
+            .collect::<Vec<_>>();
+        let mut store_w = Writer::default();
+        store_w.apply_watcher_event(&watcher::Event::Restarted(cms.clone()));
+        let period = Duration::from_secs(60);
+        let mut resync = Box::pin(trigger_resync(store_w.as_reader(), (), period));
+        assert!(poll!(resync.next()).is_pending());
+        // Nothing should be enqueued before the first period has passed
+        advance(period / 2).await;
+        assert!(poll!(resync.next()).is_pending());
+        let expected = cms.iter().map(ObjectRef::from_obj).collect::<HashSet<_>>();
+        for _ in 0..2 {
+            let mut seen = HashSet::new();
+            for _ in 0..cms.len() {
+                let request = resync.next().await.unwrap();
+                assert_eq!(request.reason, ReconcileReason::BulkReconcile);
+                assert!(seen.insert(request.obj_ref));
+            }
+            assert_eq!(seen, expected);
+        }
+    }
 }
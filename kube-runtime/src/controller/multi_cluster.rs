@@ -0,0 +1,298 @@
+//! Controller over the same kind in several clusters
+use super::{
+    applier_with_refs, trigger_self, Context, Error, QueueInspector, ReconcileReason, ReconcileRequest,
+    ReconcilerAction,
+};
+use crate::{
+    reflector::{reflector, ObjectRef, Store},
+    utils::{try_flatten_applied, CancelableJoinHandle},
+    watcher::{self, watcher},
+};
+use futures::{
+    stream::{BoxStream, SelectAll},
+    Stream, StreamExt, TryFuture, TryFutureExt, TryStreamExt,
+};
+use kube::{
+    api::{Api, ListParams, Resource},
+    Client,
+};
+use serde::de::DeserializeOwned;
+use snafu::{IntoError, Snafu};
+use std::{collections::HashMap, fmt::Debug, hash::Hash};
+use tokio::runtime::Handle;
+
+#[derive(Snafu, Debug)]
+pub enum MultiClusterQueueError {
+    #[snafu(display("failed to watch cluster {}: {}", cluster, source))]
+    WatchFailed {
+        cluster: String,
+        #[snafu(backtrace)]
+        source: watcher::Error,
+    },
+}
+
+/// The cluster that an object being reconciled by a [`MultiClusterController`] belongs to
+#[derive(Clone)]
+pub struct ClusterClient {
+    /// The identifier that the cluster was registered with
+    pub cluster: String,
+    /// A client for the cluster
+    pub client: Client,
+}
+
+/// A controller for objects of kind `K` across several clusters
+///
+/// Each cluster is registered with an identifier and an [`Api`] (and thereby a [`Client`]) using
+/// [`MultiClusterController::cluster`]. All objects are cached in a shared [`Store`], keyed by
+/// [`ObjectRef`]s that are [`in_cluster`](ObjectRef::in_cluster) of their cluster, and reconciled by
+/// the same reconciler, which is given the [`ClusterClient`] of the object's cluster.
+///
+/// ```no_run
+/// use futures::StreamExt;
+/// use k8s_openapi::api::core::v1::ConfigMap;
+/// use kube::{api::{Api, ListParams}, Client, Config};
+/// use kube_runtime::controller::{ClusterClient, Context, MultiClusterController, ReconcileReason, ReconcilerAction};
+/// use std::convert::{Infallible, TryFrom};
+///
+/// async fn reconcile(cm: ConfigMap, cluster: ClusterClient, _reason: ReconcileReason, _ctx: Context<()>) -> Result<ReconcilerAction, Infallible> {
+///     println!("checking {:?} in {}", cm.metadata.name, cluster.cluster);
+///     Ok(ReconcilerAction { requeue_after: None })
+/// }
+/// fn error_policy(err: &Infallible, _reason: &ReconcileReason, _ctx: Context<()>) -> ReconcilerAction {
+///     match *err {}
+/// }
+///
+/// # async fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut controller = MultiClusterController::<ConfigMap>::new(ListParams::default());
+/// for context in &["east", "west"] {
+///     let config = Config::from_kubeconfig(&kube::config::KubeConfigOptions {
+///         context: Some(context.to_string()),
+///         ..Default::default()
+///     })
+///     .await?;
+///     controller = controller.cluster(context, Api::all(Client::try_from(config)?));
+/// }
+/// controller
+///     .run(reconcile, error_policy, Context::new(()))
+///     .for_each(|res| async move {
+///         match res {
+///             Ok(o) => println!("reconciled {}", o.0),
+///             Err(e) => println!("reconcile failed: {}", e),
+///         }
+///     })
+///     .await;
+/// # Ok(())
+/// # }
+/// ```
+pub struct MultiClusterController<K>
+where
+    K: Clone + Resource + Debug + 'static,
+    K::DynamicType: Eq + Hash,
+{
+    selector: SelectAll<BoxStream<'static, Result<ReconcileRequest<K>, MultiClusterQueueError>>>,
+    clients: HashMap<String, Client>,
+    lp: ListParams,
+    dyntype: K::DynamicType,
+    reader: Store<K>,
+    inspector: QueueInspector<K>,
+}
+
+impl<K> MultiClusterController<K>
+where
+    K: Clone + Resource + DeserializeOwned + Debug + Send + Sync + 'static,
+    K::DynamicType: Eq + Hash + Clone + Default,
+{
+    /// Create a controller on a type `K`, without any clusters yet
+    ///
+    /// `lp` is used to watch every cluster.
+    #[must_use]
+    pub fn new(lp: ListParams) -> Self {
+        Self::new_with(lp, Default::default())
+    }
+}
+
+impl<K> MultiClusterController<K>
+where
+    K: Clone + Resource + DeserializeOwned + Debug + Send + Sync + 'static,
+    K::DynamicType: Eq + Hash + Clone,
+{
+    /// Create a controller on a type `K`, without any clusters yet
+    ///
+    /// Unlike `new`, this function accepts `K::DynamicType` so it can be used with dynamic
+    /// resources.
+    #[must_use]
+    pub fn new_with(lp: ListParams, dyntype: K::DynamicType) -> Self {
+        Self {
+            selector: SelectAll::new(),
+            clients: HashMap::new(),
+            lp,
+            dyntype,
+            reader: Store::empty(),
+            inspector: QueueInspector::new(),
+        }
+    }
+
+    /// Watch and reconcile the objects of `api`, identifying their cluster as `cluster`
+    ///
+    /// The reconciler will be given the client of `api` for these objects.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a cluster named `cluster` has already been registered.
+    #[must_use]
+    pub fn cluster(mut self, cluster: &str, api: Api<K>) -> Self {
+        assert!(
+            !self.clients.contains_key(cluster),
+            "cluster {} registered twice",
+            cluster
+        );
+        let writer = self.reader.writer_for(self.dyntype.clone()).in_cluster(cluster);
+        let id = cluster.to_string();
+        let err_id = cluster.to_string();
+        let watch = trigger_self(
+            try_flatten_applied(reflector(writer, watcher(api.clone(), self.lp.clone()))),
+            self.dyntype.clone(),
+        )
+        .map_ok(move |request| ReconcileRequest {
+            obj_ref: request.obj_ref.in_cluster(&id),
+            reason: request.reason,
+        })
+        .map_err(move |source| {
+            WatchFailed {
+                cluster: err_id.clone(),
+            }
+            .into_error(source)
+        });
+        self.selector.push(watch.boxed());
+        self.clients.insert(cluster.to_string(), api.into_client());
+        self
+    }
+
+    /// Retrieve a copy of the reader before starting the controller
+    ///
+    /// Objects must be looked up with [`ObjectRef`]s that are [`in_cluster`](ObjectRef::in_cluster).
+    #[must_use]
+    pub fn store(&self) -> Store<K> {
+        self.reader.clone()
+    }
+
+    /// Retrieve a handle for inspecting the controller's queue, see [`QueueInspector`]
+    #[must_use]
+    pub fn inspector(&self) -> QueueInspector<K> {
+        self.inspector.clone()
+    }
+
+    /// Consume the controller and start the applier stream
+    ///
+    /// This works like [`Controller::run`](super::Controller::run), except that the `reconciler`
+    /// is also passed the [`ClusterClient`] of each object.
+    ///
+    /// # Panics
+    ///
+    /// The returned stream panics if it is asked to reconcile an object that is not tagged with
+    /// one of the clusters passed to [`MultiClusterController::cluster`].
+    pub fn run<ReconcilerFut, T>(
+        self,
+        mut reconciler: impl FnMut(K, ClusterClient, ReconcileReason, Context<T>) -> ReconcilerFut,
+        error_policy: impl FnMut(&ReconcilerFut::Error, &ReconcileReason, Context<T>) -> ReconcilerAction,
+        context: Context<T>,
+    ) -> impl Stream<
+        Item = Result<(ObjectRef<K>, ReconcilerAction), Error<ReconcilerFut::Error, MultiClusterQueueError>>,
+    >
+    where
+        K::DynamicType: Debug + Unpin,
+        ReconcilerFut: TryFuture<Ok = ReconcilerAction> + Send + 'static,
+        ReconcilerFut::Error: std::error::Error + Send + 'static,
+    {
+        let clients = self.clients;
+        applier_with_refs(
+            move |obj_ref, obj, reason, ctx| {
+                // All requests are tagged with their cluster by `MultiClusterController::cluster`
+                let cluster = obj_ref
+                    .cluster()
+                    .expect("requests must have a cluster")
+                    .to_string();
+                let client = clients[&cluster].clone();
+                CancelableJoinHandle::spawn(
+                    reconciler(obj, ClusterClient { cluster, client }, reason, ctx).into_future(),
+                    &Handle::current(),
+                )
+            },
+            error_policy,
+            context,
+            self.reader,
+            self.selector,
+            self.inspector,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MultiClusterController;
+    use crate::{
+        controller::{Context, ReconcilerAction},
+        reflector::ObjectRef,
+    };
+    use futures::StreamExt;
+    use k8s_openapi::api::core::v1::ConfigMap;
+    use kube::{
+        api::{Api, ListParams, ObjectMeta},
+        fake::FakeApiServer,
+    };
+    use std::{collections::HashSet, convert::Infallible};
+
+    #[tokio::test]
+    async fn multi_cluster_controller_should_pass_client_of_object_cluster() {
+        let mut controller = MultiClusterController::<ConfigMap>::new(ListParams::default());
+        for cluster in &["a", "b"] {
+            let server = FakeApiServer::new();
+            server.insert(&ConfigMap {
+                metadata: ObjectMeta {
+                    name: Some("cm".to_string()),
+                    namespace: Some("ns".to_string()),
+                    ..ObjectMeta::default()
+                },
+                data: Some(
+                    vec![("cluster".to_string(), cluster.to_string())]
+                        .into_iter()
+                        .collect(),
+                ),
+                ..ConfigMap::default()
+            });
+            controller = controller.cluster(cluster, Api::all(server.client()));
+        }
+        let store = controller.store();
+        let mut applier = controller
+            .run(
+                |cm, cluster, _reason, _ctx| async move {
+                    // The object must be readable through the client of its own cluster
+                    let remote = Api::<ConfigMap>::namespaced(cluster.client, "ns")
+                        .get("cm")
+                        .await
+                        .unwrap();
+                    assert_eq!(remote.data, cm.data);
+                    assert_eq!(cm.data.unwrap()["cluster"], cluster.cluster);
+                    Ok::<_, Infallible>(ReconcilerAction { requeue_after: None })
+                },
+                |err, _reason, _ctx| match *err {},
+                Context::new(()),
+            )
+            .boxed();
+
+        let mut reconciled = HashSet::new();
+        for _ in 0..2 {
+            let (obj_ref, _) = applier.next().await.unwrap().unwrap();
+            reconciled.insert(obj_ref);
+        }
+        let cm_ref = ObjectRef::<ConfigMap>::new("cm").within("ns");
+        assert_eq!(
+            reconciled,
+            vec![cm_ref.clone().in_cluster("a"), cm_ref.clone().in_cluster("b")]
+                .into_iter()
+                .collect()
+        );
+        assert_eq!(store.state().len(), 2);
+        assert_eq!(store.get(&cm_ref), None);
+    }
+}
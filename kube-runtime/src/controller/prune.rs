@@ -0,0 +1,192 @@
+//! Deletes children that a controller no longer wants
+use crate::reflector::{store::Store, ObjectRef};
+use kube::{
+    api::{Api, DeleteParams, DynamicObject, Resource, ResourceExt},
+    Client,
+};
+use serde::de::DeserializeOwned;
+use snafu::{Backtrace, ResultExt, Snafu};
+use std::{collections::HashSet, fmt::Debug, hash::Hash};
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("failed to delete orphaned child {}: {}", obj_ref, source))]
+    DeleteFailed {
+        obj_ref: ObjectRef<DynamicObject>,
+        source: kube::Error,
+        backtrace: Backtrace,
+    },
+}
+
+/// Deletes the children of `owner` that are not in `desired`
+///
+/// The children are the objects in `store` that are controlled by `owner` (see
+/// [`ResourceExt::is_controlled_by`]), so `store` should be fed by a watcher on the child kind,
+/// such as the one set up by [`Controller::owns`](super::Controller::owns). Children that are already
+/// being deleted are left alone.
+///
+/// Returns the children that were deleted. When `dp.dry_run` is set, nothing is actually deleted,
+/// and the children that would have been deleted are returned instead.
+///
+/// ```no_run
+/// use k8s_openapi::api::{apps::v1::Deployment, core::v1::ConfigMap};
+/// use kube::{api::DeleteParams, Client};
+/// use kube_runtime::{controller::prune_orphans, reflector::{ObjectRef, Store}};
+///
+/// # async fn reconcile(client: Client, deploy: Deployment, config_maps: Store<ConfigMap>) -> Result<(), Box<dyn std::error::Error>> {
+/// let ns = deploy.metadata.namespace.as_deref().unwrap();
+/// // This deployment should only own `my-config`, anything else is left over from earlier versions
+/// let desired = vec![ObjectRef::new("my-config").within(ns)];
+/// let pruned = prune_orphans(client, &config_maps, &deploy, desired, &DeleteParams::default()).await?;
+/// for obj_ref in pruned {
+///     println!("pruned {}", obj_ref);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Fails on the first child that cannot be deleted. Children that have already disappeared are skipped.
+pub async fn prune_orphans<Owner, K>(
+    client: Client,
+    store: &Store<K>,
+    owner: &Owner,
+    desired: impl IntoIterator<Item = ObjectRef<K>>,
+    dp: &DeleteParams,
+) -> Result<Vec<ObjectRef<K>>, Error>
+where
+    Owner: Resource,
+    K: Resource + Clone + DeserializeOwned + Debug + 'static,
+    K::DynamicType: Default + Eq + Hash + Clone,
+{
+    prune_orphans_with(client, store, owner, desired, dp, Default::default()).await
+}
+
+/// Deletes the children of `owner` that are not in `desired`, using an explicit [`Resource::DynamicType`]
+///
+/// See [`prune_orphans`] for details.
+///
+/// # Errors
+///
+/// Fails on the first child that cannot be deleted. Children that have already disappeared are skipped.
+pub async fn prune_orphans_with<Owner, K>(
+    client: Client,
+    store: &Store<K>,
+    owner: &Owner,
+    desired: impl IntoIterator<Item = ObjectRef<K>>,
+    dp: &DeleteParams,
+    dyntype: K::DynamicType,
+) -> Result<Vec<ObjectRef<K>>, Error>
+where
+    Owner: Resource,
+    K: Resource + Clone + DeserializeOwned + Debug + 'static,
+    K::DynamicType: Eq + Hash + Clone,
+{
+    let desired = desired.into_iter().collect::<HashSet<_>>();
+    let mut pruned = Vec::new();
+    for child in store.state() {
+        if !child.is_controlled_by(owner) || child.meta().deletion_timestamp.is_some() {
+            continue;
+        }
+        let obj_ref = ObjectRef::from_obj_with(&child, dyntype.clone());
+        if desired.contains(&obj_ref) {
+            continue;
+        }
+        let api = match &obj_ref.namespace {
+            Some(ns) => Api::<K>::namespaced_with(client.clone(), ns, &dyntype),
+            None => Api::<K>::all_with(client.clone(), &dyntype),
+        };
+        match api.delete(&obj_ref.name, dp).await {
+            Ok(_) => pruned.push(obj_ref),
+            Err(err) if err.is_not_found() => {}
+            Err(err) => {
+                return Err(err).context(DeleteFailed {
+                    obj_ref: obj_ref.erase(),
+                })
+            }
+        }
+    }
+    Ok(pruned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::prune_orphans;
+    use crate::{
+        reflector::{store::Writer, ObjectRef},
+        watcher,
+    };
+    use k8s_openapi::api::core::v1::{ConfigMap, Secret};
+    use kube::{
+        api::{Api, DeleteParams, ListParams, ObjectMeta, Resource},
+        fake::FakeApiServer,
+    };
+
+    #[tokio::test]
+    async fn prune_should_only_delete_undesired_children_of_owner() {
+        let server = FakeApiServer::new();
+        let meta = |name: &str| ObjectMeta {
+            name: Some(name.to_string()),
+            namespace: Some("ns".to_string()),
+            ..ObjectMeta::default()
+        };
+        let owner = server.insert(&Secret {
+            metadata: meta("owner"),
+            ..Secret::default()
+        });
+        let other_owner = server.insert(&Secret {
+            metadata: meta("other-owner"),
+            ..Secret::default()
+        });
+        for (name, owner) in &[("keep", &owner), ("prune", &owner), ("other", &other_owner)] {
+            server.insert(&ConfigMap {
+                metadata: ObjectMeta {
+                    owner_references: Some(vec![owner.controller_owner_ref(&()).unwrap()]),
+                    ..meta(name)
+                },
+                ..ConfigMap::default()
+            });
+        }
+        let cms = Api::<ConfigMap>::namespaced(server.client(), "ns");
+        let mut store_w = Writer::default();
+        store_w.apply_watcher_event(&watcher::Event::Restarted(
+            cms.list(&ListParams::default()).await.unwrap().items,
+        ));
+        let store = store_w.as_reader();
+        let desired = || vec![ObjectRef::new("keep").within("ns")];
+        let names = |cms: Vec<ConfigMap>| {
+            cms.into_iter()
+                .map(|cm| cm.metadata.name.unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        let dry_run = DeleteParams {
+            dry_run: true,
+            ..DeleteParams::default()
+        };
+        let pruned = prune_orphans(server.client(), &store, &owner, desired(), &dry_run)
+            .await
+            .unwrap();
+        assert_eq!(pruned, vec![ObjectRef::new("prune").within("ns")]);
+        assert_eq!(
+            names(cms.list(&ListParams::default()).await.unwrap().items),
+            vec!["keep", "other", "prune"]
+        );
+
+        let pruned = prune_orphans(
+            server.client(),
+            &store,
+            &owner,
+            desired(),
+            &DeleteParams::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(pruned, vec![ObjectRef::new("prune").within("ns")]);
+        assert_eq!(
+            names(cms.list(&ListParams::default()).await.unwrap().items),
+            vec!["keep", "other"]
+        );
+    }
+}
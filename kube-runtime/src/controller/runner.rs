@@ -3,10 +3,16 @@ use crate::scheduler::{self, ScheduleRequest, Scheduler};
 use futures::{Future, Stream, StreamExt};
 use pin_project::pin_project;
 use std::{
+    collections::HashMap,
     hash::Hash,
     pin::Pin,
+    sync::{Arc, Mutex},
     task::{Context, Poll},
 };
+use tokio::time::Instant;
+
+/// The messages that are currently being processed by a [`Runner`], along with when they were started
+pub type RunningMessages<T> = Arc<Mutex<HashMap<T, Instant>>>;
 
 /// Pulls items from a [`Scheduler`], and runs an action for each item in parallel,
 /// while making sure to not process [equal](`Eq`) items multiple times at once.
@@ -20,6 +26,8 @@ pub struct Runner<T, R, F, MkF> {
     scheduler: Scheduler<T, R>,
     run_msg: MkF,
     slots: FutureHashMap<T, F>,
+    /// Mirrors the keys of `slots`, for inspection from outside of the `Runner`
+    running: RunningMessages<T>,
 }
 
 impl<T, R, F, MkF> Runner<T, R, F, MkF>
@@ -32,8 +40,15 @@ where
             scheduler,
             run_msg,
             slots: FutureHashMap::default(),
+            running: RunningMessages::default(),
         }
     }
+
+    /// Reports which messages are currently running to `running`
+    pub fn with_running(mut self, running: RunningMessages<T>) -> Self {
+        self.running = running;
+        self
+    }
 }
 
 impl<T, R, F, MkF> Stream for Runner<T, R, F, MkF>
@@ -50,7 +65,13 @@ where
         let slots = this.slots;
         let scheduler = &mut this.scheduler;
         let has_active_slots = match slots.poll_next_unpin(cx) {
-            Poll::Ready(Some(result)) => return Poll::Ready(Some(Ok(result))),
+            Poll::Ready(Some(result)) => {
+                this.running
+                    .lock()
+                    .unwrap()
+                    .retain(|msg, _| slots.contains_key(msg));
+                return Poll::Ready(Some(Ok(result)));
+            }
             Poll::Ready(None) => false,
             Poll::Pending => true,
         };
@@ -65,6 +86,7 @@ where
             match next_msg_poll {
                 Poll::Ready(Some(Ok(msg))) => {
                     let msg_fut = (this.run_msg)(&msg);
+                    this.running.lock().unwrap().insert(msg.clone(), Instant::now());
                     assert!(
                         slots.insert(msg, msg_fut).is_none(),
                         "Runner tried to replace a running future.. please report this as a kube-rs bug!"
@@ -89,11 +111,11 @@ where
 
 #[cfg(test)]
 mod tests {
-    use super::Runner;
+    use super::{Runner, RunningMessages};
     use crate::scheduler::{scheduler, ScheduleRequest};
     use futures::{
         channel::{mpsc, oneshot},
-        poll, SinkExt, TryStreamExt,
+        poll, SinkExt, StreamExt, TryStreamExt,
     };
     use std::{cell::RefCell, time::Duration};
     use tokio::{
@@ -169,4 +191,29 @@ mod tests {
             Some(8)
         );
     }
+
+    #[tokio::test]
+    async fn runner_should_report_running_messages() {
+        pause();
+        let (mut sched_tx, sched_rx) = mpsc::unbounded();
+        let (done_tx, done_rx) = oneshot::channel::<()>();
+        let mut done_rx = Some(done_rx);
+        let running = RunningMessages::default();
+        let mut runner = Box::pin(
+            Runner::new(scheduler(sched_rx), |_| done_rx.take().unwrap()).with_running(running.clone()),
+        );
+        sched_tx
+            .send(ScheduleRequest {
+                message: 1_u8,
+                run_at: Instant::now(),
+            })
+            .await
+            .unwrap();
+        let started_at = Instant::now();
+        assert!(poll!(runner.next()).is_pending());
+        assert_eq!(running.lock().unwrap().get(&1), Some(&started_at));
+        done_tx.send(()).unwrap();
+        assert!(runner.next().await.unwrap().unwrap().is_ok());
+        assert!(running.lock().unwrap().is_empty());
+    }
 }
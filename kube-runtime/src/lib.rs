@@ -18,9 +18,11 @@
 #![allow(clippy::type_repetition_in_bounds)]
 
 pub mod controller;
+#[cfg(feature = "recording")] pub mod recording;
 pub mod reflector;
 pub mod scheduler;
 pub mod utils;
+pub mod wait;
 pub mod watcher;
 
 pub use controller::{applier, Controller};
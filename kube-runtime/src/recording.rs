@@ -0,0 +1,254 @@
+//! Records [`watcher`](crate::watcher()) events, and replays them later
+//!
+//! Recordings are stored as JSON lines, with one [`RecordedEvent`] per line. This makes it possible
+//! to capture the events seen by a production controller, and feed them into a [`reflector`](crate::reflector())
+//! or [`applier`](crate::applier()) in a test to reproduce a bug:
+//!
+//! ```no_run
+//! use futures::TryStreamExt;
+//! use k8s_openapi::api::core::v1::Pod;
+//! use kube::{api::ListParams, Api, Client};
+//! use kube_runtime::{recording, reflector, reflector::store::Writer, watcher};
+//! use std::{fs::File, io::BufReader};
+//!
+//! # async fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+//! // In production
+//! let pods = Api::<Pod>::all(Client::try_default().await?);
+//! let file = tokio::io::BufWriter::new(tokio::fs::File::create("pods.jsonl").await?);
+//! recording::record(watcher(pods, ListParams::default()), file)
+//!     .try_for_each(|_| async { Ok(()) })
+//!     .await?;
+//!
+//! // Later, in a test
+//! let events = recording::read_recording::<Pod>(BufReader::new(File::open("pods.jsonl")?))?;
+//! let writer = Writer::<Pod>::default();
+//! let store = writer.as_reader();
+//! reflector(writer, recording::replay(events, recording::ReplayTiming::Original))
+//!     .try_for_each(|_| async { Ok(()) })
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::watcher;
+use futures::{stream, Stream, StreamExt};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::{io::BufRead, time::Duration};
+use tokio::{
+    io::{AsyncWrite, AsyncWriteExt},
+    time::Instant,
+};
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("watch failed: {}", source))]
+    WatchFailed {
+        #[snafu(backtrace)]
+        source: watcher::Error,
+    },
+    #[snafu(display("failed to serialize event: {}", source))]
+    SerializeFailed { source: serde_json::Error },
+    #[snafu(display("failed to write event: {}", source))]
+    WriteFailed { source: std::io::Error },
+    #[snafu(display("failed to read recording: {}", source))]
+    ReadFailed { source: std::io::Error },
+    #[snafu(display("failed to parse line {} of recording: {}", line, source))]
+    ParseFailed { line: usize, source: serde_json::Error },
+}
+
+/// A single line of a recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedEvent<K> {
+    /// When the event was received, relative to the first event of the recording
+    #[serde(rename = "offsetMillis", with = "duration_millis")]
+    pub offset: Duration,
+    /// The recorded event
+    pub event: watcher::Event<K>,
+}
+
+mod duration_millis {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::{convert::TryFrom, time::Duration};
+
+    #[allow(clippy::trivially_copy_pass_by_ref)] // Signature is required by serde
+    pub fn serialize<S: Serializer>(duration: &Duration, ser: S) -> Result<S::Ok, S::Error> {
+        ser.serialize_u64(u64::try_from(duration.as_millis()).unwrap_or(u64::MAX))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(de: D) -> Result<Duration, D::Error> {
+        u64::deserialize(de).map(Duration::from_millis)
+    }
+}
+
+/// Records all events from `stream` to `writer`, while passing them through
+///
+/// Each event is written (and flushed) as soon as it is received. Errors from `stream` are passed
+/// through, but not recorded.
+pub fn record<K, S, W>(stream: S, writer: W) -> impl Stream<Item = Result<watcher::Event<K>, Error>>
+where
+    K: Serialize,
+    S: Stream<Item = watcher::Result<watcher::Event<K>>>,
+    W: AsyncWrite + Unpin,
+{
+    stream::unfold(
+        (Box::pin(stream), writer, None),
+        |(mut stream, mut writer, mut start)| async move {
+            let event = stream.next().await?;
+            let recorded = record_event(&mut writer, &mut start, event).await;
+            Some((recorded, (stream, writer, start)))
+        },
+    )
+}
+
+async fn record_event<K: Serialize>(
+    writer: &mut (impl AsyncWrite + Unpin),
+    start: &mut Option<Instant>,
+    event: watcher::Result<watcher::Event<K>>,
+) -> Result<watcher::Event<K>, Error> {
+    let event = event.context(WatchFailed)?;
+    let start = *start.get_or_insert_with(Instant::now);
+    let recorded = RecordedEvent {
+        offset: start.elapsed(),
+        event,
+    };
+    let mut line = serde_json::to_vec(&recorded).context(SerializeFailed)?;
+    line.push(b'\n');
+    writer.write_all(&line).await.context(WriteFailed)?;
+    writer.flush().await.context(WriteFailed)?;
+    Ok(recorded.event)
+}
+
+/// Reads a recording that was written by [`record`]
+///
+/// Blank lines are ignored.
+///
+/// # Errors
+///
+/// Fails if `reader` cannot be read, or if any line is not a valid [`RecordedEvent`].
+pub fn read_recording<K: DeserializeOwned>(reader: impl BufRead) -> Result<Vec<RecordedEvent<K>>, Error> {
+    let mut events = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.context(ReadFailed)?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        events.push(serde_json::from_str(&line).context(ParseFailed { line: i + 1 })?);
+    }
+    Ok(events)
+}
+
+/// How fast [`replay`] emits events
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayTiming {
+    /// Keep the original delays between events
+    ///
+    /// Delays are measured using Tokio's clock, so [pausing](tokio::time::pause) the clock makes replays
+    /// both instant and deterministic.
+    Original,
+    /// Emit events as soon as they are requested
+    Immediate,
+}
+
+/// Replays recorded events as a [`watcher`](crate::watcher()) stream
+///
+/// The stream ends after the last event.
+pub fn replay<K>(
+    events: Vec<RecordedEvent<K>>,
+    timing: ReplayTiming,
+) -> impl Stream<Item = watcher::Result<watcher::Event<K>>> {
+    stream::unfold(
+        (events.into_iter(), None),
+        move |(mut events, start): (_, Option<Instant>)| async move {
+            let recorded = events.next()?;
+            let start = start.unwrap_or_else(Instant::now);
+            if timing == ReplayTiming::Original {
+                tokio::time::sleep_until(start + recorded.offset).await;
+            }
+            Some((Ok(recorded.event), (events, Some(start))))
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_recording, record, replay, ReplayTiming};
+    use crate::{
+        reflector::{reflector, store::Writer},
+        watcher,
+    };
+    use futures::{stream, StreamExt, TryStreamExt};
+    use k8s_openapi::{api::core::v1::ConfigMap, apimachinery::pkg::apis::meta::v1::ObjectMeta};
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    fn cm(name: &str) -> ConfigMap {
+        ConfigMap {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                ..ObjectMeta::default()
+            },
+            ..ConfigMap::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn replay_should_reproduce_recorded_events_and_timing() {
+        tokio::time::pause();
+        let events = vec![
+            watcher::Event::Restarted(vec![cm("a"), cm("b")]),
+            watcher::Event::Deleted(cm("a")),
+            watcher::Event::Applied(cm("c")),
+        ];
+        let mut buf = Vec::new();
+        let recorded = record(
+            stream::iter(events).then(|event| async {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                Ok(event)
+            }),
+            &mut buf,
+        )
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+        assert_eq!(recorded.len(), 3);
+
+        let recording = read_recording::<ConfigMap>(buf.as_slice()).unwrap();
+        // Tokio rounds timers up to the next millisecond
+        assert_eq!(
+            recording
+                .iter()
+                .map(|event| event.offset.as_secs())
+                .collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+
+        let writer = Writer::<ConfigMap>::default();
+        let store = writer.as_reader();
+        let start = Instant::now();
+        reflector(writer, replay(recording, ReplayTiming::Original))
+            .try_for_each(|_| async { Ok(()) })
+            .await
+            .unwrap();
+        assert_eq!(start.elapsed().as_secs(), 2);
+        let mut names = store
+            .state()
+            .into_iter()
+            .map(|cm| cm.metadata.name.unwrap())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["b", "c"]);
+    }
+
+    #[test]
+    fn events_should_serialize_with_type_tag() {
+        assert_eq!(
+            serde_json::to_value(watcher::Event::Deleted(cm("a"))).unwrap(),
+            serde_json::json!({
+                "type": "Deleted",
+                "object": { "apiVersion": "v1", "kind": "ConfigMap", "metadata": { "name": "a" } },
+            })
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use super::store::Writer;
+use crate::watcher;
+use futures::{stream, Stream, TryStreamExt};
+use kube::Resource;
+use std::hash::Hash;
+
+/// An object that was added or modified, along with its previous state
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectChange<K> {
+    /// The object as it was cached before the change, or `None` if it is new to the cache
+    pub old: Option<K>,
+    /// The object after the change
+    pub new: K,
+}
+
+impl<K> ObjectChange<K> {
+    /// Calculates a JSON patch from `old` to `new`
+    ///
+    /// If `old` is `None` then the patch replaces the whole document.
+    ///
+    /// # Errors
+    ///
+    /// Fails if either object cannot be serialized into JSON.
+    #[cfg(feature = "jsonpatch")]
+    pub fn diff(&self) -> Result<json_patch::Patch, serde_json::Error>
+    where
+        K: serde::Serialize,
+    {
+        let old = match &self.old {
+            Some(old) => serde_json::to_value(old)?,
+            None => serde_json::Value::Null,
+        };
+        Ok(json_patch::diff(&old, &serde_json::to_value(&self.new)?))
+    }
+}
+
+/// Caches objects from `watcher::Event`s to a local `Store`, like [`reflector`](super::reflector),
+/// and emits every added or modified object alongside its previously cached state
+///
+/// Every object in a `Restarted` event is emitted, even if it didn't change. Deleted objects are not emitted.
+pub fn reflector_changes<K, W>(
+    mut store: Writer<K>,
+    stream: W,
+) -> impl Stream<Item = watcher::Result<ObjectChange<K>>>
+where
+    K: Resource + Clone,
+    K::DynamicType: Eq + Hash + Clone,
+    W: Stream<Item = watcher::Result<watcher::Event<K>>>,
+{
+    let reader = store.as_reader();
+    stream
+        .map_ok(move |event| {
+            // Look up the old states before the event overwrites them
+            let changes = event
+                .clone()
+                .into_iter_applied()
+                .map(|new| ObjectChange {
+                    old: reader.get(&store.obj_ref(&new)),
+                    new,
+                })
+                .collect::<Vec<_>>();
+            store.apply_watcher_event(&event);
+            stream::iter(changes.into_iter().map(Ok))
+        })
+        .try_flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{reflector_changes, ObjectChange};
+    use crate::{reflector::store::Writer, watcher};
+    use futures::{stream, TryStreamExt};
+    use k8s_openapi::{api::core::v1::ConfigMap, apimachinery::pkg::apis::meta::v1::ObjectMeta};
+
+    fn cm(name: &str, value: &str) -> ConfigMap {
+        ConfigMap {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                ..ObjectMeta::default()
+            },
+            data: Some(
+                vec![("value".to_string(), value.to_string())]
+                    .into_iter()
+                    .collect(),
+            ),
+            ..ConfigMap::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn changes_should_include_previous_state() {
+        let changes = reflector_changes(
+            Writer::default(),
+            stream::iter(vec![
+                Ok(watcher::Event::Applied(cm("a", "1"))),
+                Ok(watcher::Event::Applied(cm("a", "2"))),
+                Ok(watcher::Event::Deleted(cm("a", "2"))),
+                Ok(watcher::Event::Restarted(vec![cm("a", "3"), cm("b", "1")])),
+                Ok(watcher::Event::Restarted(vec![cm("b", "2")])),
+            ]),
+        )
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+        assert_eq!(changes, vec![
+            ObjectChange {
+                old: None,
+                new: cm("a", "1")
+            },
+            ObjectChange {
+                old: Some(cm("a", "1")),
+                new: cm("a", "2")
+            },
+            ObjectChange {
+                old: None,
+                new: cm("a", "3")
+            },
+            ObjectChange {
+                old: None,
+                new: cm("b", "1")
+            },
+            ObjectChange {
+                old: Some(cm("b", "1")),
+                new: cm("b", "2")
+            },
+        ]);
+    }
+
+    #[cfg(feature = "jsonpatch")]
+    #[test]
+    fn diff_should_only_include_changed_fields() {
+        let change = ObjectChange {
+            old: Some(cm("a", "1")),
+            new: cm("a", "2"),
+        };
+        assert_eq!(
+            serde_json::to_value(change.diff().unwrap()).unwrap(),
+            serde_json::json!([{ "op": "replace", "path": "/data/value", "value": "2" }])
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use super::ObjectRef;
+use crate::watcher;
+use derivative::Derivative;
+use kube::Resource;
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    sync::{Arc, Mutex},
+};
+
+/// Internal state of a [`ReferenceIndex`]
+#[derive(Derivative)]
+#[derivative(Default(bound = ""))]
+struct Index<K: Resource, Ref: Resource> {
+    /// Every object of kind `K` that refers to a given `Ref`
+    referrers: HashMap<ObjectRef<Ref>, HashSet<ObjectRef<K>>>,
+    /// Every `Ref` that a given object of kind `K` refers to, used to clean up `referrers`
+    references: HashMap<ObjectRef<K>, Vec<ObjectRef<Ref>>>,
+}
+
+impl<K: Resource, Ref: Resource> Index<K, Ref>
+where
+    K::DynamicType: Eq + Hash + Clone,
+    Ref::DynamicType: Eq + Hash + Clone,
+{
+    fn remove(&mut self, obj_ref: &ObjectRef<K>) {
+        for reference in self.references.remove(obj_ref).into_iter().flatten() {
+            if let Some(referrers) = self.referrers.get_mut(&reference) {
+                referrers.remove(obj_ref);
+                if referrers.is_empty() {
+                    self.referrers.remove(&reference);
+                }
+            }
+        }
+    }
+
+    fn insert(&mut self, obj_ref: ObjectRef<K>, references: Vec<ObjectRef<Ref>>) {
+        self.remove(&obj_ref);
+        for reference in &references {
+            self.referrers
+                .entry(reference.clone())
+                .or_default()
+                .insert(obj_ref.clone());
+        }
+        self.references.insert(obj_ref, references);
+    }
+}
+
+/// A reverse index from objects of kind `Ref` to the objects of kind `K` that refer to them
+///
+/// For example, this can be used to find every object whose `spec` refers to a given `ConfigMap`, without
+/// having to scan every object in the [`Store`](super::Store). The references are extracted from each `K`
+/// by a user-supplied function, and kept up to date by feeding it the same [`watcher::Event`]s that are
+/// applied to the `Store`.
+///
+/// Cloning will produce a new reference to the same index.
+///
+/// ```
+/// use k8s_openapi::api::{apps::v1::Deployment, core::v1::ConfigMap};
+/// use kube::api::ResourceExt;
+/// use kube_runtime::{reflector::{index::ReferenceIndex, ObjectRef}, watcher};
+///
+/// // Index the ConfigMaps that each Deployment mounts as a volume
+/// let index = ReferenceIndex::<Deployment, ConfigMap>::new((), |deploy: &Deployment| {
+///     let ns = deploy.namespace().unwrap_or_default();
+///     deploy.spec.iter()
+///         .filter_map(|spec| spec.template.spec.as_ref())
+///         .flat_map(|pod_spec| pod_spec.volumes.iter().flatten())
+///         .filter_map(|volume| volume.config_map.as_ref()?.name.as_deref())
+///         .map(|cm_name| ObjectRef::new(cm_name).within(&ns))
+///         .collect::<Vec<_>>()
+/// });
+/// # let deploy = Deployment {
+/// #     metadata: kube::api::ObjectMeta { name: Some("my-deploy".to_string()), ..Default::default() },
+/// #     ..Default::default()
+/// # };
+/// index.apply_watcher_event(&watcher::Event::Applied(deploy));
+/// let referrers = index.referrers(&ObjectRef::new("my-config").within("default"));
+/// ```
+#[derive(Derivative)]
+#[derivative(Clone(bound = "K::DynamicType: Clone"))]
+pub struct ReferenceIndex<K: Resource, Ref: Resource> {
+    dyntype: K::DynamicType,
+    #[allow(clippy::type_complexity)]
+    extractor: Arc<dyn Fn(&K) -> Vec<ObjectRef<Ref>> + Send + Sync>,
+    index: Arc<Mutex<Index<K, Ref>>>,
+}
+
+impl<K, Ref> ReferenceIndex<K, Ref>
+where
+    K: Resource,
+    K::DynamicType: Eq + Hash + Clone,
+    Ref: Resource,
+    Ref::DynamicType: Eq + Hash + Clone,
+{
+    /// Creates an empty index, where `extractor` returns the objects that a given `K` refers to
+    pub fn new<I>(dyntype: K::DynamicType, extractor: impl Fn(&K) -> I + Send + Sync + 'static) -> Self
+    where
+        I: IntoIterator<Item = ObjectRef<Ref>>,
+    {
+        ReferenceIndex {
+            dyntype,
+            extractor: Arc::new(move |obj| extractor(obj).into_iter().collect()),
+            index: Arc::default(),
+        }
+    }
+
+    /// Applies a single watcher event to the index
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index's lock has been poisoned by a panicking `extractor`.
+    pub fn apply_watcher_event(&self, event: &watcher::Event<K>) {
+        let mut index = self.index.lock().unwrap();
+        match event {
+            watcher::Event::Applied(obj) => {
+                index.insert(
+                    ObjectRef::from_obj_with(obj, self.dyntype.clone()),
+                    (self.extractor)(obj),
+                );
+            }
+            watcher::Event::Deleted(obj) => {
+                index.remove(&ObjectRef::from_obj_with(obj, self.dyntype.clone()));
+            }
+            watcher::Event::Restarted(objs) => {
+                *index = Index::default();
+                for obj in objs {
+                    index.insert(
+                        ObjectRef::from_obj_with(obj, self.dyntype.clone()),
+                        (self.extractor)(obj),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Returns every `K` that currently refers to `reference`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index's lock has been poisoned by a panicking `extractor`.
+    #[must_use]
+    pub fn referrers(&self, reference: &ObjectRef<Ref>) -> Vec<ObjectRef<K>> {
+        self.index
+            .lock()
+            .unwrap()
+            .referrers
+            .get(reference)
+            .map(|referrers| referrers.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReferenceIndex;
+    use crate::{reflector::ObjectRef, watcher};
+    use k8s_openapi::api::core::v1::{ConfigMap, Secret};
+    use kube::api::{ObjectMeta, ResourceExt};
+    use std::collections::BTreeMap;
+
+    /// A `Secret` that refers to the `ConfigMap`s listed (comma-separated) in its `refs` annotation
+    fn secret(name: &str, refs: &str) -> Secret {
+        let mut annotations = BTreeMap::new();
+        annotations.insert("refs".to_string(), refs.to_string());
+        Secret {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                namespace: Some("ns".to_string()),
+                annotations: Some(annotations),
+                ..ObjectMeta::default()
+            },
+            ..Secret::default()
+        }
+    }
+
+    fn index() -> ReferenceIndex<Secret, ConfigMap> {
+        ReferenceIndex::new((), |secret: &Secret| {
+            let ns = secret.namespace().unwrap();
+            secret.annotations()["refs"]
+                .split(',')
+                .filter(|name| !name.is_empty())
+                .map(|name| ObjectRef::new(name).within(&ns))
+                .collect::<Vec<_>>()
+        })
+    }
+
+    fn referrers(index: &ReferenceIndex<Secret, ConfigMap>, cm_name: &str) -> Vec<String> {
+        let mut referrers = index
+            .referrers(&ObjectRef::new(cm_name).within("ns"))
+            .into_iter()
+            .map(|obj_ref| obj_ref.name)
+            .collect::<Vec<_>>();
+        referrers.sort();
+        referrers
+    }
+
+    #[test]
+    fn index_should_find_referrers() {
+        let index = index();
+        index.apply_watcher_event(&watcher::Event::Applied(secret("a", "cm1,cm2")));
+        index.apply_watcher_event(&watcher::Event::Applied(secret("b", "cm2")));
+        assert_eq!(referrers(&index, "cm1"), vec!["a"]);
+        assert_eq!(referrers(&index, "cm2"), vec!["a", "b"]);
+        assert!(referrers(&index, "cm3").is_empty());
+    }
+
+    #[test]
+    fn index_should_forget_stale_references() {
+        let index = index();
+        index.apply_watcher_event(&watcher::Event::Applied(secret("a", "cm1,cm2")));
+        index.apply_watcher_event(&watcher::Event::Applied(secret("b", "cm2")));
+        index.apply_watcher_event(&watcher::Event::Applied(secret("a", "cm3")));
+        assert!(referrers(&index, "cm1").is_empty());
+        assert_eq!(referrers(&index, "cm2"), vec!["b"]);
+        assert_eq!(referrers(&index, "cm3"), vec!["a"]);
+        index.apply_watcher_event(&watcher::Event::Deleted(secret("b", "cm2")));
+        assert!(referrers(&index, "cm2").is_empty());
+    }
+
+    #[test]
+    fn index_should_be_replaced_on_restart() {
+        let index = index();
+        index.apply_watcher_event(&watcher::Event::Applied(secret("a", "cm1")));
+        index.apply_watcher_event(&watcher::Event::Restarted(vec![secret("b", "cm1")]));
+        assert_eq!(referrers(&index, "cm1"), vec!["b"]);
+    }
+}
@@ -1,9 +1,14 @@
 //! Caches objects in memory
 
+mod changes;
+pub mod index;
 mod object_ref;
 pub mod store;
 
-pub use self::object_ref::ObjectRef;
+pub use self::{
+    changes::{reflector_changes, ObjectChange},
+    object_ref::ObjectRef,
+};
 use crate::watcher;
 use futures::{Stream, TryStreamExt};
 use kube::Resource;
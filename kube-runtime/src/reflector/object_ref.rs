@@ -44,6 +44,8 @@ pub struct ObjectRef<K: Resource> {
     /// assert_ne!(ObjectRef::<ConfigMap>::new("foo"), ObjectRef::new("foo").within("bar"));
     /// ```
     pub namespace: Option<String>,
+    /// The cluster that the object lives in, see [`ObjectRef::in_cluster`]
+    cluster: Option<String>,
 }
 
 impl<K: Resource> ObjectRef<K>
@@ -71,6 +73,7 @@ impl<K: Resource> ObjectRef<K> {
             dyntype,
             name: name.into(),
             namespace: None,
+            cluster: None,
         }
     }
 
@@ -80,6 +83,23 @@ impl<K: Resource> ObjectRef<K> {
         self
     }
 
+    /// Refer to the object in the cluster identified by `cluster`
+    ///
+    /// Only relevant when objects from several clusters are mixed, such as by a
+    /// [`MultiClusterController`](crate::controller::MultiClusterController). References to
+    /// objects in different clusters are never equal, even if they are otherwise identical.
+    #[must_use]
+    pub fn in_cluster(mut self, cluster: &str) -> Self {
+        self.cluster = Some(cluster.to_string());
+        self
+    }
+
+    /// The cluster that the object lives in, if it was set by [`ObjectRef::in_cluster`]
+    #[must_use]
+    pub fn cluster(&self) -> Option<&str> {
+        self.cluster.as_deref()
+    }
+
     /// Creates ObjectRef from the resource and dynamic type.
     /// Panics if name is missing (name always exists if the object
     /// was returned from the apiserver)
@@ -92,6 +112,7 @@ impl<K: Resource> ObjectRef<K> {
             dyntype,
             name: obj.name(),
             namespace: obj.namespace(),
+            cluster: None,
         }
     }
 
@@ -109,6 +130,7 @@ impl<K: Resource> ObjectRef<K> {
                 dyntype,
                 name: owner.name.clone(),
                 namespace: namespace.map(String::from),
+                cluster: None,
             })
         } else {
             None
@@ -125,14 +147,35 @@ impl<K: Resource> ObjectRef<K> {
             dyntype: dt2,
             name: self.name,
             namespace: self.namespace,
+            cluster: self.cluster,
         }
     }
 
+    /// Create an `OwnerReference` pointing to the referenced object
+    ///
+    /// `ObjectRef`s do not keep track of uids, so the uid of the object must be supplied separately.
+    #[must_use]
+    pub fn to_owner_ref(&self, uid: &str) -> OwnerReference {
+        OwnerReference {
+            api_version: K::api_version(&self.dyntype).into_owned(),
+            kind: K::kind(&self.dyntype).into_owned(),
+            name: self.name.clone(),
+            uid: uid.to_string(),
+            ..OwnerReference::default()
+        }
+    }
+
+    /// The dynamic type of the referenced object
+    pub(crate) fn dyntype(&self) -> &K::DynamicType {
+        &self.dyntype
+    }
+
     pub fn erase(self) -> ObjectRef<DynamicObject> {
         ObjectRef {
             dyntype: kube::api::ApiResource::erase::<K>(&self.dyntype),
             name: self.name,
             namespace: self.namespace,
+            cluster: self.cluster,
         }
     }
 }
@@ -150,6 +193,9 @@ impl<K: Resource> Display for ObjectRef<K> {
         if let Some(namespace) = &self.namespace {
             write!(f, ".{}", namespace)?;
         }
+        if let Some(cluster) = &self.cluster {
+            write!(f, "@{}", cluster)?;
+        }
         Ok(())
     }
 }
@@ -179,6 +225,15 @@ mod tests {
             format!("{}", ObjectRef::<Node>::new("my-node")),
             "Node.v1./my-node"
         );
+        assert_eq!(
+            format!(
+                "{}",
+                ObjectRef::<Pod>::new("my-pod")
+                    .within("my-namespace")
+                    .in_cluster("my-cluster")
+            ),
+            "Pod.v1./my-pod.my-namespace@my-cluster"
+        );
     }
 
     #[test]
@@ -190,4 +245,22 @@ mod tests {
         let node_ref = ObjectRef::<Node>::new("my-node");
         assert_eq!(format!("{}", node_ref), format!("{}", node_ref.erase()));
     }
+
+    #[test]
+    fn owner_ref_should_round_trip() {
+        let deploy_ref = ObjectRef::<Deployment>::new("my-deploy").within("my-namespace");
+        let owner_ref = deploy_ref.to_owner_ref("1234");
+        assert_eq!(
+            (
+                owner_ref.api_version.as_str(),
+                owner_ref.kind.as_str(),
+                owner_ref.uid.as_str()
+            ),
+            ("apps/v1", "Deployment", "1234")
+        );
+        assert_eq!(
+            ObjectRef::from_owner_ref(Some("my-namespace"), &owner_ref, ()),
+            Some(deploy_ref)
+        );
+    }
 }
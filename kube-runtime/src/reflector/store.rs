@@ -17,6 +17,7 @@ where
 {
     store: Arc<DashMap<ObjectRef<K>, K>>,
     dyntype: K::DynamicType,
+    cluster: Option<String>,
 }
 
 impl<K: 'static + Resource + Clone> Writer<K>
@@ -31,6 +32,25 @@ where
         Writer {
             store: Default::default(),
             dyntype,
+            cluster: None,
+        }
+    }
+
+    /// Tags all objects written by this writer with the cluster identifier `cluster`
+    ///
+    /// The objects must then be looked up with [`ObjectRef`]s that are [`in_cluster`](ObjectRef::in_cluster)
+    /// as well.
+    #[must_use]
+    pub fn in_cluster(mut self, cluster: &str) -> Self {
+        self.cluster = Some(cluster.to_string());
+        self
+    }
+
+    pub(crate) fn obj_ref(&self, obj: &K) -> ObjectRef<K> {
+        let obj_ref = ObjectRef::from_obj_with(obj, self.dyntype.clone());
+        match &self.cluster {
+            Some(cluster) => obj_ref.in_cluster(cluster),
+            None => obj_ref,
         }
     }
 
@@ -49,20 +69,24 @@ where
     pub fn apply_watcher_event(&mut self, event: &watcher::Event<K>) {
         match event {
             watcher::Event::Applied(obj) => {
-                self.store
-                    .insert(ObjectRef::from_obj_with(&obj, self.dyntype.clone()), obj.clone());
+                self.store.insert(self.obj_ref(obj), obj.clone());
             }
             watcher::Event::Deleted(obj) => {
-                self.store
-                    .remove(&ObjectRef::from_obj_with(&obj, self.dyntype.clone()));
+                self.store.remove(&self.obj_ref(obj));
             }
             watcher::Event::Restarted(new_objs) => {
                 let new_objs = new_objs
                     .iter()
-                    .map(|obj| (ObjectRef::from_obj_with(obj, self.dyntype.clone()), obj))
+                    .map(|obj| (self.obj_ref(obj), obj))
                     .collect::<HashMap<_, _>>();
                 // We can't do do the whole replacement atomically, but we should at least not delete objects that still exist
-                self.store.retain(|key, _old_value| new_objs.contains_key(key));
+                // Objects of other types or clusters belong to other writers sharing the store, see `Store::writer_for`
+                let (dyntype, cluster) = (&self.dyntype, &self.cluster);
+                self.store.retain(|key, _old_value| {
+                    key.dyntype() != dyntype
+                        || key.cluster() != cluster.as_deref()
+                        || new_objs.contains_key(key)
+                });
                 for (key, obj) in new_objs {
                     self.store.insert(key, obj.clone());
                 }
@@ -115,6 +139,25 @@ where
             .map(|entry| entry.value().clone())
     }
 
+    /// Creates an empty store, to be filled by writers from [`Store::writer_for`]
+    pub(crate) fn empty() -> Self {
+        Store {
+            store: Default::default(),
+        }
+    }
+
+    /// Creates a [`Writer`] for objects of type `dyntype` that writes into this store
+    ///
+    /// The writer's `Restarted` events only replace objects of the same `dyntype` (and cluster), so several
+    /// writers may share a store as long as they each have a different `dyntype` or [`Writer::in_cluster`].
+    pub(crate) fn writer_for(&self, dyntype: K::DynamicType) -> Writer<K> {
+        Writer {
+            store: self.store.clone(),
+            dyntype,
+            cluster: None,
+        }
+    }
+
     /// Return a full snapshot of the current values
     #[must_use]
     pub fn state(&self) -> Vec<K> {
@@ -124,7 +167,7 @@ where
 
 #[cfg(test)]
 mod tests {
-    use super::Writer;
+    use super::{Store, Writer};
     use crate::{reflector::ObjectRef, watcher};
     use k8s_openapi::api::core::v1::ConfigMap;
     use kube::api::ObjectMeta;
@@ -196,4 +239,26 @@ mod tests {
         let store = store_w.as_reader();
         assert_eq!(store.get(&ObjectRef::from_obj(&nsed_cm)), Some(cm));
     }
+
+    #[test]
+    fn should_keep_objects_of_different_clusters_apart() {
+        let cm = ConfigMap {
+            metadata: ObjectMeta {
+                name: Some("obj".to_string()),
+                namespace: Some("ns".to_string()),
+                ..ObjectMeta::default()
+            },
+            ..ConfigMap::default()
+        };
+        let store = Store::empty();
+        let mut store_a = store.writer_for(()).in_cluster("a");
+        let mut store_b = store.writer_for(()).in_cluster("b");
+        store_a.apply_watcher_event(&watcher::Event::Applied(cm.clone()));
+        store_b.apply_watcher_event(&watcher::Event::Applied(cm.clone()));
+        store_b.apply_watcher_event(&watcher::Event::Restarted(Vec::new()));
+        let obj_ref = ObjectRef::from_obj(&cm);
+        assert_eq!(store.get(&obj_ref.clone().in_cluster("a")), Some(cm));
+        assert_eq!(store.get(&obj_ref.clone().in_cluster("b")), None);
+        assert_eq!(store.get(&obj_ref), None);
+    }
 }
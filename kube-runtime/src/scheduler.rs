@@ -1,5 +1,6 @@
 //! Delays and deduplicates [`Stream`] items
 
+use derivative::Derivative;
 use futures::{
     stream::{Fuse, FusedStream},
     Stream, StreamExt,
@@ -7,9 +8,10 @@ use futures::{
 use pin_project::pin_project;
 use snafu::{Backtrace, ResultExt, Snafu};
 use std::{
-    collections::{hash_map::Entry, HashMap, HashSet},
+    collections::{HashMap, HashSet},
     hash::Hash,
     pin::Pin,
+    sync::{Arc, Mutex},
     task::{Context, Poll},
 };
 use tokio::time::{self, Instant};
@@ -26,7 +28,7 @@ pub enum Error {
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 /// A request to re-emit `message` at a given `Instant` (`run_at`).
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ScheduleRequest<T> {
     pub message: T,
     pub run_at: Instant,
@@ -38,6 +40,66 @@ struct ScheduledEntry {
     queue_key: delay_queue::Key,
 }
 
+/// Internal metadata for all messages known by a [`Scheduler`].
+#[derive(Derivative)]
+#[derivative(Default(bound = ""))]
+struct SchedulerMetadata<T> {
+    /// Metadata for all currently scheduled messages. Used to detect duplicate messages.
+    scheduled: HashMap<T, ScheduledEntry>,
+    /// Messages that are scheduled to have happened, but have been held using `hold_unless`.
+    pending: HashSet<T>,
+}
+
+/// A read-only handle for inspecting the messages held by a [`Scheduler`], see [`Scheduler::inspector`]
+///
+/// Cloning will produce a new reference to the same [`Scheduler`].
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""))]
+pub struct SchedulerInspector<T> {
+    metadata: Arc<Mutex<SchedulerMetadata<T>>>,
+}
+
+impl<T> SchedulerInspector<T> {
+    /// Creates the metadata for a new [`Scheduler`], which must not be shared with any other `Scheduler`
+    pub(crate) fn new() -> Self {
+        Self {
+            metadata: Arc::default(),
+        }
+    }
+}
+
+impl<T: Clone> SchedulerInspector<T> {
+    /// Returns all messages that are currently waiting for their `run_at` time, ordered by `run_at`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the scheduler's lock has been poisoned by a panic while it was held.
+    #[must_use]
+    pub fn scheduled(&self) -> Vec<ScheduleRequest<T>> {
+        let metadata = self.metadata.lock().unwrap();
+        let mut scheduled = metadata
+            .scheduled
+            .iter()
+            .map(|(message, entry)| ScheduleRequest {
+                message: message.clone(),
+                run_at: entry.run_at,
+            })
+            .collect::<Vec<_>>();
+        scheduled.sort_by_key(|request| request.run_at);
+        scheduled
+    }
+
+    /// Returns all messages that are due, but are currently held pending by [`Scheduler::hold_unless`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if the scheduler's lock has been poisoned by a panic while it was held.
+    #[must_use]
+    pub fn pending(&self) -> Vec<T> {
+        self.metadata.lock().unwrap().pending.iter().cloned().collect()
+    }
+}
+
 #[pin_project(project = SchedulerProj)]
 pub struct Scheduler<T, R> {
     /// Queue of already-scheduled messages.
@@ -45,21 +107,20 @@ pub struct Scheduler<T, R> {
     /// To ensure that the metadata is kept up-to-date, use `schedule_message` and
     /// `poll_pop_queue_message` rather than manipulating this directly.
     queue: DelayQueue<T>,
-    /// Metadata for all currently scheduled messages. Used to detect duplicate messages.
-    scheduled: HashMap<T, ScheduledEntry>,
-    /// Messages that are scheduled to have happened, but have been held using `hold_unless`.
-    pending: HashSet<T>,
+    /// Metadata for all scheduled and pending messages.
+    ///
+    /// Shared with any [`SchedulerInspector`]s, but only ever locked inside of a single `poll`.
+    metadata: SchedulerInspector<T>,
     /// Incoming queue of scheduling requests.
     #[pin]
     requests: Fuse<R>,
 }
 
 impl<T, R: Stream> Scheduler<T, R> {
-    fn new(requests: R) -> Self {
+    fn new(requests: R, metadata: SchedulerInspector<T>) -> Self {
         Self {
             queue: DelayQueue::new(),
-            scheduled: HashMap::new(),
-            pending: HashSet::new(),
+            metadata,
             requests: requests.fuse(),
         }
     }
@@ -68,32 +129,37 @@ impl<T, R: Stream> Scheduler<T, R> {
 impl<'a, T: Hash + Eq + Clone, R> SchedulerProj<'a, T, R> {
     /// Attempt to schedule a message into the queue.
     ///
-    /// If the message is already in the queue then the earlier `request.run_at` takes precedence.
+    /// If the message is already in the queue then the earlier `request.run_at` takes precedence,
+    /// along with the message that was sent with it. This only matters for messages that compare equal
+    /// while carrying extra data that isn't considered by [`Eq`] (such as a reason for the request).
     fn schedule_message(&mut self, request: ScheduleRequest<T>) {
-        if self.pending.contains(&request.message) {
+        let mut metadata = self.metadata.metadata.lock().unwrap();
+        if metadata.pending.contains(&request.message) {
             // Message is already pending, so we can't even expedite it
             return;
         }
-        match self.scheduled.entry(request.message) {
-            Entry::Occupied(mut old_entry) if old_entry.get().run_at >= request.run_at => {
+        match metadata.scheduled.get(&request.message) {
+            Some(old_entry) if old_entry.run_at < request.run_at => {
+                // Old entry will run before the new request, so ignore the new request..
+                return;
+            }
+            Some(_old_entry) => {
                 // Old entry will run after the new request, so replace it..
-                let entry = old_entry.get_mut();
+                // The old entry must be removed first, since `HashMap::insert` keeps the old key
                 // TODO: this should add a little delay here to actually debounce
-                self.queue.reset_at(&entry.queue_key, request.run_at);
-                entry.run_at = request.run_at;
-            }
-            Entry::Occupied(_old_entry) => {
-                // Old entry will run before the new request, so ignore the new request..
+                if let Some(old_entry) = metadata.scheduled.remove(&request.message) {
+                    self.queue.remove(&old_entry.queue_key);
+                }
             }
-            Entry::Vacant(entry) => {
+            None => {
                 // No old entry, we're free to go!
-                let message = entry.key().clone();
-                entry.insert(ScheduledEntry {
-                    run_at: request.run_at,
-                    queue_key: self.queue.insert_at(message, request.run_at),
-                });
             }
         }
+        let message = request.message.clone();
+        metadata.scheduled.insert(request.message, ScheduledEntry {
+            run_at: request.run_at,
+            queue_key: self.queue.insert_at(message, request.run_at),
+        });
     }
 
     /// Attempt to retrieve a message from the queue.
@@ -102,25 +168,31 @@ impl<'a, T: Hash + Eq + Clone, R> SchedulerProj<'a, T, R> {
         cx: &mut Context<'_>,
         can_take_message: impl Fn(&T) -> bool,
     ) -> Poll<Option<Result<T, time::error::Error>>> {
-        if let Some(msg) = self.pending.iter().find(|msg| can_take_message(*msg)).cloned() {
-            return Poll::Ready(Some(Ok(self.pending.take(&msg).unwrap())));
+        let mut metadata = self.metadata.metadata.lock().unwrap();
+        if let Some(msg) = metadata
+            .pending
+            .iter()
+            .find(|msg| can_take_message(*msg))
+            .cloned()
+        {
+            return Poll::Ready(Some(Ok(metadata.pending.take(&msg).unwrap())));
         }
 
         loop {
             match self.queue.poll_expired(cx) {
                 Poll::Ready(Some(Ok(msg))) => {
                     let msg = msg.into_inner();
-                    self.scheduled.remove(&msg).expect(
+                    metadata.scheduled.remove(&msg).expect(
                     "Expired message was popped from the Scheduler queue, but was not in the metadata map",
                 );
                     if can_take_message(&msg) {
                         break Poll::Ready(Some(Ok(msg)));
                     }
-                    self.pending.insert(msg);
+                    metadata.pending.insert(msg);
                 }
                 Poll::Ready(Some(Err(err))) => break Poll::Ready(Some(Err(err))),
                 Poll::Ready(None) => {
-                    break if self.pending.is_empty() {
+                    break if metadata.pending.is_empty() {
                         Poll::Ready(None)
                     } else {
                         // There are still remaining pending messages, so we're not done quite yet..
@@ -194,10 +266,23 @@ where
         }
     }
 
+    /// Returns a handle that can be used to inspect the scheduled and pending messages
+    ///
+    /// This is intended for debugging, for example to list the contents of the queue on a debug HTTP endpoint.
+    /// The handle stays valid (but empty) after the `Scheduler` has been dropped.
+    #[must_use]
+    pub fn inspector(&self) -> SchedulerInspector<T> {
+        self.metadata.clone()
+    }
+
     /// Checks whether `msg` is currently a pending message (held by `hold_unless`)
+    ///
+    /// # Panics
+    ///
+    /// Panics if the scheduler's lock has been poisoned by a panic while it was held.
     #[cfg(test)]
     pub fn contains_pending(&self, msg: &T) -> bool {
-        self.pending.contains(msg)
+        self.metadata.metadata.lock().unwrap().pending.contains(msg)
     }
 }
 
@@ -222,14 +307,28 @@ where
 /// that is already pending will be discarded (since it is already going to be emitted as soon as the consumer
 /// is ready for it).
 pub fn scheduler<T: Eq + Hash + Clone, S: Stream<Item = ScheduleRequest<T>>>(requests: S) -> Scheduler<T, S> {
-    Scheduler::new(requests)
+    Scheduler::new(requests, SchedulerInspector::new())
+}
+
+/// Variant of [`scheduler`] that reports its state to an existing [`SchedulerInspector`]
+///
+/// This allows the inspector to be handed out before the `Scheduler` itself is constructed. The `inspector`
+/// must have been created by [`SchedulerInspector::new`], and must not be passed to any other `Scheduler`.
+pub(crate) fn scheduler_with_inspector<T: Eq + Hash + Clone, S: Stream<Item = ScheduleRequest<T>>>(
+    requests: S,
+    inspector: SchedulerInspector<T>,
+) -> Scheduler<T, S> {
+    Scheduler::new(requests, inspector)
 }
 
 #[cfg(test)]
 mod tests {
     use super::{scheduler, ScheduleRequest};
     use futures::{channel::mpsc, poll, stream, FutureExt, SinkExt, StreamExt};
-    use std::task::Poll;
+    use std::{
+        hash::{Hash, Hasher},
+        task::Poll,
+    };
     use tokio::time::{advance, pause, Duration, Instant};
 
     fn unwrap_poll<T>(poll: Poll<T>) -> T {
@@ -398,4 +497,89 @@ mod tests {
         scheduler.next().now_or_never().unwrap().unwrap().unwrap();
         assert!(poll!(scheduler.next()).is_pending());
     }
+
+    #[tokio::test]
+    async fn scheduler_dedupe_should_emit_message_of_earliest_request() {
+        // Messages are equal if their keys are equal, regardless of their payloads
+        #[derive(Debug, Clone)]
+        struct Msg(u8, &'static str);
+        impl PartialEq for Msg {
+            fn eq(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+        impl Eq for Msg {}
+        impl Hash for Msg {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.0.hash(state)
+            }
+        }
+
+        pause();
+        let mut scheduler = scheduler(stream::iter(vec![
+            ScheduleRequest {
+                message: Msg(1, "late"),
+                run_at: Instant::now() + Duration::from_secs(3),
+            },
+            ScheduleRequest {
+                message: Msg(1, "early"),
+                run_at: Instant::now() + Duration::from_secs(1),
+            },
+            ScheduleRequest {
+                message: Msg(1, "latest"),
+                run_at: Instant::now() + Duration::from_secs(5),
+            },
+        ]));
+        assert!(poll!(scheduler.next()).is_pending());
+        advance(Duration::from_secs(2)).await;
+        assert_eq!(
+            scheduler.next().now_or_never().unwrap().unwrap().unwrap().1,
+            "early"
+        );
+        // Stream has terminated
+        assert!(scheduler.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn scheduler_inspector_should_list_scheduled_and_pending_items() {
+        pause();
+        let now = Instant::now();
+        let mut scheduler = Box::pin(scheduler(stream::iter(vec![
+            ScheduleRequest {
+                message: 1_u8,
+                run_at: now + Duration::from_secs(3),
+            },
+            ScheduleRequest {
+                message: 2,
+                run_at: now + Duration::from_secs(1),
+            },
+        ])));
+        let inspector = scheduler.inspector();
+        assert!(poll!(scheduler.as_mut().hold_unless(|_| false).next()).is_pending());
+        let scheduled = inspector.scheduled();
+        assert_eq!(
+            scheduled
+                .iter()
+                .map(|req| (req.message, req.run_at))
+                .collect::<Vec<_>>(),
+            vec![
+                (2, now + Duration::from_secs(1)),
+                (1, now + Duration::from_secs(3))
+            ]
+        );
+        assert!(inspector.pending().is_empty());
+        advance(Duration::from_secs(2)).await;
+        assert!(poll!(scheduler.as_mut().hold_unless(|_| false).next()).is_pending());
+        assert_eq!(
+            inspector
+                .scheduled()
+                .iter()
+                .map(|req| req.message)
+                .collect::<Vec<_>>(),
+            vec![1]
+        );
+        assert_eq!(inspector.pending(), vec![2]);
+        assert_eq!(scheduler.next().await.unwrap().unwrap(), 2);
+        assert!(inspector.pending().is_empty());
+    }
 }
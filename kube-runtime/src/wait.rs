@@ -0,0 +1,282 @@
+//! Waits for objects to reach desired states
+//!
+//! [`await_condition`] watches a single object until a [`Condition`] is fulfilled, and
+//! [`await_condition_timeout`] gives up if that takes too long:
+//!
+//! ```no_run
+//! use k8s_openapi::api::core::v1::Pod;
+//! use kube::{Api, Client};
+//! use kube_runtime::wait::{await_condition_timeout, conditions};
+//! use std::time::Duration;
+//!
+//! # async fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+//! let pods = Api::<Pod>::namespaced(Client::try_default().await?, "default");
+//! let pod = await_condition_timeout(pods, "my-pod", conditions::is_pod_running(), Duration::from_secs(30)).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::watcher;
+use futures::{StreamExt, TryStreamExt};
+use kube::{
+    api::{ListParams, Resource},
+    Api,
+};
+use serde::de::DeserializeOwned;
+use snafu::{ResultExt, Snafu};
+use std::{fmt::Debug, time::Duration};
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("failed to probe for whether the condition is fulfilled yet: {}", source))]
+    ProbeFailed {
+        #[snafu(backtrace)]
+        source: watcher::Error,
+    },
+    #[snafu(display("condition was not fulfilled within {:?}", timeout))]
+    TimedOut { timeout: Duration },
+}
+
+/// A predicate over the state of a single object
+///
+/// The object is `None` if it doesn't exist (anymore). Any `Fn(Option<&K>) -> bool` is a `Condition`,
+/// and a few common ones are available in the [`conditions`] module.
+pub trait Condition<K> {
+    /// Returns whether the condition is fulfilled for `obj`
+    fn matches_object(&self, obj: Option<&K>) -> bool;
+}
+
+impl<K, F: Fn(Option<&K>) -> bool> Condition<K> for F {
+    fn matches_object(&self, obj: Option<&K>) -> bool {
+        (self)(obj)
+    }
+}
+
+/// Waits until the object named `name` fulfills `cond`
+///
+/// Returns the object in the state that fulfilled the condition, or `None` if the condition
+/// was fulfilled by the object not existing.
+///
+/// # Errors
+///
+/// Fails if the object cannot be watched.
+pub async fn await_condition<K>(api: Api<K>, name: &str, cond: impl Condition<K>) -> Result<Option<K>, Error>
+where
+    K: Resource + Clone + DeserializeOwned + Debug + Send + 'static,
+{
+    let lp = ListParams::default().fields(&format!("metadata.name={}", name));
+    let mut events = watcher(api, lp).boxed();
+    while let Some(event) = events.try_next().await.context(ProbeFailed)? {
+        // The field selector ensures that every event is about the same object
+        let obj = match event {
+            watcher::Event::Applied(obj) => Some(obj),
+            watcher::Event::Deleted(_) => None,
+            watcher::Event::Restarted(objs) => objs.into_iter().last(),
+        };
+        if cond.matches_object(obj.as_ref()) {
+            return Ok(obj);
+        }
+    }
+    unreachable!("watcher streams never end")
+}
+
+/// Waits until the object named `name` fulfills `cond`, for at most `timeout`
+///
+/// Returns the same as [`await_condition`].
+///
+/// # Errors
+///
+/// Fails with [`Error::TimedOut`] if the condition is not fulfilled within `timeout`, and if the object cannot
+/// be watched.
+pub async fn await_condition_timeout<K>(
+    api: Api<K>,
+    name: &str,
+    cond: impl Condition<K>,
+    timeout: Duration,
+) -> Result<Option<K>, Error>
+where
+    K: Resource + Clone + DeserializeOwned + Debug + Send + 'static,
+{
+    match tokio::time::timeout(timeout, await_condition(api, name, cond)).await {
+        Ok(res) => res,
+        Err(_) => TimedOut { timeout }.fail(),
+    }
+}
+
+/// Common [`Condition`]s
+pub mod conditions {
+    use super::Condition;
+    use k8s_openapi::api::{apps::v1::Deployment, batch::v1::Job, core::v1::Pod};
+    use kube::Resource;
+
+    /// An object that has been deleted, or replaced by another object of the same name
+    ///
+    /// `uid` should be the uid of the object that is being waited on.
+    #[must_use]
+    pub fn is_deleted<K: Resource>(uid: &str) -> impl Condition<K> + '_ {
+        move |obj: Option<&K>| obj.map_or(true, |obj| obj.meta().uid.as_deref() != Some(uid))
+    }
+
+    k8s_openapi::k8s_if_ge_1_16! {
+        use k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinition;
+
+        /// A `CustomResourceDefinition` whose `Established` condition is `True`, meaning that it is being served
+        #[must_use]
+        pub fn is_crd_established() -> impl Condition<CustomResourceDefinition> {
+            |obj: Option<&CustomResourceDefinition>| {
+                obj.and_then(|crd| crd.status.as_ref())
+                    .and_then(|status| status.conditions.as_ref())
+                    .map_or(false, |conditions| {
+                        conditions
+                            .iter()
+                            .any(|cond| cond.type_ == "Established" && cond.status == "True")
+                    })
+            }
+        }
+    }
+
+    /// A `Pod` whose phase is `Running`
+    #[must_use]
+    pub fn is_pod_running() -> impl Condition<Pod> {
+        |obj: Option<&Pod>| {
+            obj.and_then(|pod| pod.status.as_ref())
+                .and_then(|status| status.phase.as_deref())
+                == Some("Running")
+        }
+    }
+
+    /// A `Job` whose `Complete` condition is `True`
+    #[must_use]
+    pub fn is_job_completed() -> impl Condition<Job> {
+        |obj: Option<&Job>| {
+            obj.and_then(|job| job.status.as_ref())
+                .and_then(|status| status.conditions.as_ref())
+                .map_or(false, |conditions| {
+                    conditions
+                        .iter()
+                        .any(|cond| cond.type_ == "Complete" && cond.status == "True")
+                })
+        }
+    }
+
+    /// A `Deployment` whose latest generation has been fully rolled out
+    ///
+    /// This follows the same rules as `kubectl rollout status`: the controller must have observed the
+    /// latest `spec`, and all replicas must be updated and available.
+    #[must_use]
+    pub fn is_deployment_rolled_out() -> impl Condition<Deployment> {
+        |obj: Option<&Deployment>| {
+            let deployment = match obj {
+                Some(deployment) => deployment,
+                None => return false,
+            };
+            let status = match &deployment.status {
+                Some(status) => status,
+                None => return false,
+            };
+            let desired = deployment
+                .spec
+                .as_ref()
+                .and_then(|spec| spec.replicas)
+                .unwrap_or(1);
+            let updated = status.updated_replicas.unwrap_or_default();
+            status.observed_generation >= deployment.metadata.generation
+                && updated >= desired
+                && status.replicas.unwrap_or_default() <= updated
+                && status.available_replicas.unwrap_or_default() >= updated
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{await_condition, await_condition_timeout, conditions, Condition, Error};
+    use k8s_openapi::api::{
+        apps::v1::{Deployment, DeploymentSpec, DeploymentStatus},
+        core::v1::Pod,
+    };
+    use kube::{
+        api::{DeleteParams, ObjectMeta, Patch, PatchParams},
+        fake::FakeApiServer,
+        Api,
+    };
+    use serde_json::json;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn await_condition_should_wait_for_pod_to_run_and_be_deleted() {
+        let server = FakeApiServer::new();
+        let pod = server.insert(&Pod {
+            metadata: ObjectMeta {
+                name: Some("pod".to_string()),
+                namespace: Some("ns".to_string()),
+                ..ObjectMeta::default()
+            },
+            ..Pod::default()
+        });
+        let pods = Api::<Pod>::namespaced(server.client(), "ns");
+        let running = tokio::spawn(await_condition(pods.clone(), "pod", conditions::is_pod_running()));
+        pods.patch_status(
+            "pod",
+            &PatchParams::default(),
+            &Patch::Merge(json!({ "status": { "phase": "Running" } })),
+        )
+        .await
+        .unwrap();
+        assert!(running.await.unwrap().unwrap().is_some());
+
+        let uid = pod.metadata.uid.unwrap();
+        let deleted =
+            tokio::spawn(async move { await_condition(pods, "pod", conditions::is_deleted(&uid)).await });
+        Api::<Pod>::namespaced(server.client(), "ns")
+            .delete("pod", &DeleteParams::default())
+            .await
+            .unwrap();
+        assert!(deleted.await.unwrap().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn await_condition_timeout_should_time_out() {
+        tokio::time::pause();
+        let server = FakeApiServer::new();
+        server.insert(&Pod {
+            metadata: ObjectMeta {
+                name: Some("pod".to_string()),
+                namespace: Some("ns".to_string()),
+                ..ObjectMeta::default()
+            },
+            ..Pod::default()
+        });
+        let pods = Api::<Pod>::namespaced(server.client(), "ns");
+        let timeout = Duration::from_secs(30);
+        let res = await_condition_timeout(pods, "pod", conditions::is_pod_running(), timeout).await;
+        assert!(matches!(res, Err(Error::TimedOut { timeout: t }) if t == timeout));
+    }
+
+    #[test]
+    fn deployment_should_only_be_rolled_out_once_all_replicas_are_updated() {
+        let deployment = |observed_generation, replicas, updated, available| Deployment {
+            metadata: ObjectMeta {
+                generation: Some(2),
+                ..ObjectMeta::default()
+            },
+            spec: Some(DeploymentSpec {
+                replicas: Some(2),
+                ..DeploymentSpec::default()
+            }),
+            status: Some(DeploymentStatus {
+                observed_generation: Some(observed_generation),
+                replicas: Some(replicas),
+                updated_replicas: Some(updated),
+                available_replicas: Some(available),
+                ..DeploymentStatus::default()
+            }),
+        };
+        let cond = conditions::is_deployment_rolled_out();
+        assert!(!cond.matches_object(None));
+        assert!(!cond.matches_object(Some(&deployment(1, 2, 2, 2))));
+        assert!(!cond.matches_object(Some(&deployment(2, 3, 2, 2))));
+        assert!(!cond.matches_object(Some(&deployment(2, 2, 2, 1))));
+        assert!(cond.matches_object(Some(&deployment(2, 2, 2, 2))));
+    }
+}
@@ -3,10 +3,10 @@
 use derivative::Derivative;
 use futures::{stream::BoxStream, Stream, StreamExt};
 use kube::{
-    api::{ListParams, Resource, ResourceExt, WatchEvent},
+    api::{ListParams, Resource, ResourceExt, WatchEvent, WatchParams},
     Api,
 };
-use serde::de::DeserializeOwned;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use smallvec::SmallVec;
 use snafu::{Backtrace, ResultExt, Snafu};
 use std::{clone::Clone, fmt::Debug};
@@ -36,8 +36,11 @@ pub enum Error {
 }
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "object")]
 /// Watch events returned from the [`watcher`]
+///
+/// Events can be serialized, for example to record them with the `recording` feature.
 pub enum Event<K> {
     /// An object was added or modified
     Applied(K),
@@ -88,7 +91,16 @@ impl<K> Event<K> {
 /// The internal finite state machine driving the [`watcher`]
 enum State<K: Resource + Clone> {
     /// The Watcher is empty, and the next [`poll`](Stream::poll_next) will start the initial LIST to get all existing objects
+    ///
+    /// If the watcher sends initial events then the next poll starts the watch instead, moving on to `InitialEvents`.
     Empty,
+    /// The watch was started with [`WatchParams::send_initial_events`], so we collect the existing objects until the
+    /// bookmark that marks the end of the initial events, and then move on to `Watching`.
+    InitialEvents {
+        objects: Vec<K>,
+        #[derivative(Debug = "ignore")]
+        stream: BoxStream<'static, kube::Result<WatchEvent<K>>>,
+    },
     /// The initial LIST was successful, so we should move on to starting the actual watch.
     InitListed { resource_version: String },
     /// The watch is in progress, from this point we just return events from the server.
@@ -104,31 +116,79 @@ enum State<K: Resource + Clone> {
     },
 }
 
+/// The parameters of the calls made by the [`watcher`]
+struct Params {
+    list: ListParams,
+    /// Derived from `list` if `None`
+    watch: Option<WatchParams>,
+}
+
+impl Params {
+    /// The parameters of a watch that starts with the initial events, if the watcher sends them
+    fn initial_events(&self) -> Option<&WatchParams> {
+        self.watch.as_ref().filter(|wp| wp.send_initial_events)
+    }
+
+    /// The parameters of a watch that resumes from a known resource version
+    ///
+    /// Only the watch-related parts of the [`ListParams`] are used, so pagination is ignored.
+    fn resume(&self) -> WatchParams {
+        match &self.watch {
+            Some(wp) => WatchParams {
+                send_initial_events: false,
+                ..wp.clone()
+            },
+            None => WatchParams {
+                label_selector: self.list.label_selector.clone(),
+                field_selector: self.list.field_selector.clone(),
+                timeout: self.list.timeout,
+                bookmarks: self.list.bookmarks,
+                send_initial_events: false,
+            },
+        }
+    }
+}
+
+/// The annotation of the bookmark that marks the end of the initial events of a watch
+const INITIAL_EVENTS_END: &str = "k8s.io/initial-events-end";
+
 /// Progresses the watcher a single step, returning (event, state)
 ///
 /// This function should be trampolined: if event == `None`
 /// then the function should be called again until it returns a Some.
 async fn step_trampolined<K: Resource + Clone + DeserializeOwned + Debug + Send + 'static>(
     api: &Api<K>,
-    list_params: &ListParams,
+    params: &Params,
     state: State<K>,
 ) -> (Option<Result<Event<K>>>, State<K>) {
     match state {
-        State::Empty => match api.list(&list_params).await {
-            Ok(list) => (Some(Ok(Event::Restarted(list.items))), State::InitListed {
-                resource_version: list.metadata.resource_version.unwrap(),
-            }),
-            Err(err) => (Some(Err(err).context(InitialListFailed)), State::Empty),
-        },
-        State::InitListed { resource_version } => match api.watch(&list_params, &resource_version).await {
-            Ok(stream) => (None, State::Watching {
-                resource_version,
-                stream: stream.boxed(),
-            }),
-            Err(err) => (Some(Err(err).context(WatchStartFailed)), State::InitListed {
-                resource_version,
-            }),
+        State::Empty => match params.initial_events() {
+            Some(wp) => match api.watch(wp, "").await {
+                Ok(stream) => (None, State::InitialEvents {
+                    objects: Vec::new(),
+                    stream: stream.boxed(),
+                }),
+                Err(err) => (Some(Err(err).context(WatchStartFailed)), State::Empty),
+            },
+            None => match api.list(&params.list).await {
+                Ok(list) => (Some(Ok(Event::Restarted(list.items))), State::InitListed {
+                    resource_version: list.metadata.resource_version.unwrap(),
+                }),
+                Err(err) => (Some(Err(err).context(InitialListFailed)), State::Empty),
+            },
         },
+        State::InitialEvents { objects, stream } => step_initial_events(objects, stream).await,
+        State::InitListed { resource_version } => {
+            match api.watch(&params.resume(), &resource_version).await {
+                Ok(stream) => (None, State::Watching {
+                    resource_version,
+                    stream: stream.boxed(),
+                }),
+                Err(err) => (Some(Err(err).context(WatchStartFailed)), State::InitListed {
+                    resource_version,
+                }),
+            }
+        }
         State::Watching {
             resource_version,
             mut stream,
@@ -172,14 +232,52 @@ async fn step_trampolined<K: Resource + Clone + DeserializeOwned + Debug + Send
     }
 }
 
+/// Progresses the watcher a single step while it collects the initial events, see `State::InitialEvents`
+async fn step_initial_events<K: Resource + Clone>(
+    mut objects: Vec<K>,
+    mut stream: BoxStream<'static, kube::Result<WatchEvent<K>>>,
+) -> (Option<Result<Event<K>>>, State<K>) {
+    match stream.next().await {
+        Some(Ok(WatchEvent::Added(obj))) | Some(Ok(WatchEvent::Modified(obj))) => {
+            objects.retain(|known| known.meta().uid != obj.meta().uid);
+            objects.push(obj);
+            (None, State::InitialEvents { objects, stream })
+        }
+        Some(Ok(WatchEvent::Deleted(obj))) => {
+            objects.retain(|known| known.meta().uid != obj.meta().uid);
+            (None, State::InitialEvents { objects, stream })
+        }
+        Some(Ok(WatchEvent::Bookmark(bm)))
+            if bm
+                .metadata
+                .annotations
+                .get(INITIAL_EVENTS_END)
+                .map(String::as_str)
+                == Some("true") =>
+        {
+            (Some(Ok(Event::Restarted(objects))), State::Watching {
+                resource_version: bm.metadata.resource_version,
+                stream,
+            })
+        }
+        Some(Ok(WatchEvent::Bookmark(_))) => (None, State::InitialEvents { objects, stream }),
+        Some(Ok(WatchEvent::Error(err))) => {
+            // The initial events can't be resumed, so any disruption starts them over
+            (Some(Err(err).context(WatchError)), State::Empty)
+        }
+        Some(Err(err)) => (Some(Err(err).context(WatchFailed)), State::Empty),
+        None => (None, State::Empty),
+    }
+}
+
 /// Trampoline helper for `step_trampolined`
 async fn step<K: Resource + Clone + DeserializeOwned + Debug + Send + 'static>(
     api: &Api<K>,
-    list_params: &ListParams,
+    params: &Params,
     mut state: State<K>,
 ) -> (Result<Event<K>>, State<K>) {
     loop {
-        match step_trampolined(&api, &list_params, state).await {
+        match step_trampolined(&api, params, state).await {
             (Some(result), new_state) => return (result, new_state),
             (None, new_state) => state = new_state,
         }
@@ -218,6 +316,15 @@ async fn step<K: Resource + Clone + DeserializeOwned + Debug + Send + 'static>(
 ///    Ok(())
 /// }
 /// ```
+///
+/// The initial list honors [`ListParams::resource_version`], so [`ListParams::match_any`] lets it be served from
+/// the API server's watch cache rather than from etcd. This is much cheaper for large collections, but the listed
+/// state may be slightly stale (the watch then catches up from there).
+///
+/// The watch is made with the watch-related parts of `list_params`, so [`ListParams::limit`] and
+/// [`ListParams::continue_token`] only apply to the initial list. Use [`watcher_with`] to control the
+/// [`WatchParams`] directly.
+///
 /// [`try_flatten_applied`]: super::utils::try_flatten_applied
 /// [`reflector`]: super::reflector::reflector
 /// [`Api::watch`]: https://docs.rs/kube/*/kube/struct.Api.html#method.watch
@@ -236,11 +343,100 @@ pub fn watcher<K: Resource + Clone + DeserializeOwned + Debug + Send + 'static>(
     api: Api<K>,
     list_params: ListParams,
 ) -> impl Stream<Item = Result<Event<K>>> + Send {
-    futures::stream::unfold(
-        (api, list_params, State::Empty),
-        |(api, list_params, state)| async {
-            let (event, state) = step(&api, &list_params, state).await;
-            Some((event, (api, list_params, state)))
-        },
-    )
+    run(api, Params {
+        list: list_params,
+        watch: None,
+    })
+}
+
+/// Watches a Kubernetes Resource for changes continuously, like [`watcher`], with the given [`WatchParams`]
+///
+/// The initial list uses the selectors and timeout of `watch_params`.
+///
+/// With [`WatchParams::send_initial_events`], the initial list is skipped: the existing objects are streamed by
+/// the watch itself, and are emitted as an [`Event::Restarted`] once the API server has sent all of them. This is
+/// cheaper for the API server than a list, but requires the `WatchList` feature gate.
+pub fn watcher_with<K: Resource + Clone + DeserializeOwned + Debug + Send + 'static>(
+    api: Api<K>,
+    watch_params: WatchParams,
+) -> impl Stream<Item = Result<Event<K>>> + Send {
+    let list_params = ListParams {
+        label_selector: watch_params.label_selector.clone(),
+        field_selector: watch_params.field_selector.clone(),
+        timeout: watch_params.timeout,
+        bookmarks: watch_params.bookmarks,
+        ..ListParams::default()
+    };
+    run(api, Params {
+        list: list_params,
+        watch: Some(watch_params),
+    })
+}
+
+fn run<K: Resource + Clone + DeserializeOwned + Debug + Send + 'static>(
+    api: Api<K>,
+    params: Params,
+) -> impl Stream<Item = Result<Event<K>>> + Send {
+    futures::stream::unfold((api, params, State::Empty), |(api, params, state)| async {
+        let (event, state) = step(&api, &params, state).await;
+        Some((event, (api, params, state)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{watcher, watcher_with, Event};
+    use futures::{StreamExt, TryStreamExt};
+    use k8s_openapi::api::core::v1::ConfigMap;
+    use kube::{
+        api::{ListParams, ObjectMeta, PostParams, ResourceExt, WatchParams},
+        fake::FakeApiServer,
+        Api,
+    };
+
+    fn configmap(name: &str) -> ConfigMap {
+        ConfigMap {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                namespace: Some("ns".to_string()),
+                ..ObjectMeta::default()
+            },
+            ..ConfigMap::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn watcher_with_initial_events_should_restart_with_the_existing_objects() {
+        let server = FakeApiServer::new();
+        server.insert(&configmap("a"));
+        server.insert(&configmap("b"));
+        let cms = Api::<ConfigMap>::namespaced(server.client(), "ns");
+        let mut events = watcher_with(cms.clone(), WatchParams::default().initial_events()).boxed();
+        match events.try_next().await.unwrap() {
+            Some(Event::Restarted(objs)) => {
+                assert_eq!(objs.iter().map(ResourceExt::name).collect::<Vec<_>>(), vec![
+                    "a", "b"
+                ]);
+            }
+            other => panic!("expected the initial objects, got {:?}", other),
+        }
+        cms.create(&PostParams::default(), &configmap("c")).await.unwrap();
+        match events.try_next().await.unwrap() {
+            Some(Event::Applied(obj)) => assert_eq!(obj.name(), "c"),
+            other => panic!("expected c to be applied, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn watcher_should_watch_with_paginated_list_params() {
+        let server = FakeApiServer::new();
+        let cms = Api::<ConfigMap>::namespaced(server.client(), "ns");
+        let mut events = watcher(cms.clone(), ListParams::default().limit(10)).boxed();
+        assert!(matches!(events.try_next().await, Ok(Some(Event::Restarted(_)))));
+        cms.create(&PostParams::default(), &configmap("a")).await.unwrap();
+        match events.try_next().await.unwrap() {
+            Some(Event::Applied(obj)) => assert_eq!(obj.name(), "a"),
+            other => panic!("expected a to be applied, got {:?}", other),
+        }
+    }
 }
@@ -0,0 +1,247 @@
+//! Typed status conditions, following the conventions of `metav1.Condition`
+use k8s_openapi::{
+    apimachinery::pkg::apis::meta::v1::{ObjectMeta, Time},
+    chrono::Utc,
+};
+use serde::{Deserialize, Serialize};
+
+/// The status of a [`Condition`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum ConditionStatus {
+    /// The condition applies
+    True,
+    /// The condition does not apply
+    False,
+    /// It is not known whether the condition applies
+    Unknown,
+}
+
+/// A single aspect of the current state of an object, as found in `status.conditions`
+///
+/// This mirrors [`metav1.Condition`](https://pkg.go.dev/k8s.io/apimachinery/pkg/apis/meta/v1#Condition),
+/// and is intended to be embedded in the status of custom resources.
+/// Conditions should be updated with [`set_status_condition`] (or [`Api::patch_status_condition`](crate::Api::patch_status_condition)),
+/// which takes care of only moving `last_transition_time` when the status actually changes.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct Condition {
+    /// The type of the condition, in CamelCase (such as `Ready`)
+    #[serde(rename = "type")]
+    pub type_: String,
+    /// Whether the condition applies
+    pub status: ConditionStatus,
+    /// The `metadata.generation` that the condition was set based upon
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub observed_generation: Option<i64>,
+    /// The last time that the status changed
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
+    pub last_transition_time: Time,
+    /// A programmatic identifier for the reason of the last transition, in CamelCase
+    pub reason: String,
+    /// A human readable message describing the transition
+    #[serde(default)]
+    pub message: String,
+}
+
+impl Condition {
+    /// Creates a condition that transitioned now
+    pub fn new(
+        type_: impl Into<String>,
+        status: ConditionStatus,
+        reason: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Condition {
+            type_: type_.into(),
+            status,
+            observed_generation: None,
+            last_transition_time: Time(Utc::now()),
+            reason: reason.into(),
+            message: message.into(),
+        }
+    }
+
+    /// Record that the condition was set based upon the `metadata.generation` of `meta`
+    pub fn observing(mut self, meta: &ObjectMeta) -> Self {
+        self.observed_generation = meta.generation;
+        self
+    }
+}
+
+/// Finds the condition of type `type_`
+pub fn find_status_condition<'a>(conditions: &'a [Condition], type_: &str) -> Option<&'a Condition> {
+    conditions.iter().find(|cond| cond.type_ == type_)
+}
+
+/// Whether the condition of type `type_` exists and is `True`
+pub fn is_status_condition_true(conditions: &[Condition], type_: &str) -> bool {
+    find_status_condition(conditions, type_).map_or(false, |cond| cond.status == ConditionStatus::True)
+}
+
+/// Adds or updates the condition of the same type as `condition`
+///
+/// The `last_transition_time` of an existing condition is only updated if its status changes.
+/// Returns whether `conditions` was changed.
+pub fn set_status_condition(conditions: &mut Vec<Condition>, condition: Condition) -> bool {
+    let existing = match conditions.iter_mut().find(|cond| cond.type_ == condition.type_) {
+        Some(existing) => existing,
+        None => {
+            conditions.push(condition);
+            return true;
+        }
+    };
+    let last_transition_time = if existing.status == condition.status {
+        existing.last_transition_time.clone()
+    } else {
+        condition.last_transition_time.clone()
+    };
+    let updated = Condition {
+        last_transition_time,
+        ..condition
+    };
+    if *existing == updated {
+        return false;
+    }
+    *existing = updated;
+    true
+}
+
+/// Removes the condition of type `type_`
+///
+/// Returns whether `conditions` was changed.
+pub fn remove_status_condition(conditions: &mut Vec<Condition>, type_: &str) -> bool {
+    let len = conditions.len();
+    conditions.retain(|cond| cond.type_ != type_);
+    conditions.len() != len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::chrono::{TimeZone, Utc};
+
+    fn condition(type_: &str, status: ConditionStatus, reason: &str, at: i64) -> Condition {
+        Condition {
+            last_transition_time: Time(Utc.timestamp(at, 0)),
+            ..Condition::new(type_, status, reason, "")
+        }
+    }
+
+    #[test]
+    fn set_should_only_move_transition_time_on_status_change() {
+        let mut conditions = Vec::new();
+        assert!(set_status_condition(
+            &mut conditions,
+            condition("Ready", ConditionStatus::False, "Starting", 1)
+        ));
+        assert!(set_status_condition(
+            &mut conditions,
+            condition("Ready", ConditionStatus::False, "StillStarting", 2)
+        ));
+        assert_eq!(conditions, vec![condition(
+            "Ready",
+            ConditionStatus::False,
+            "StillStarting",
+            1
+        )]);
+        assert!(!set_status_condition(
+            &mut conditions,
+            condition("Ready", ConditionStatus::False, "StillStarting", 3)
+        ));
+        assert!(set_status_condition(
+            &mut conditions,
+            condition("Ready", ConditionStatus::True, "Started", 4)
+        ));
+        assert_eq!(conditions, vec![condition(
+            "Ready",
+            ConditionStatus::True,
+            "Started",
+            4
+        )]);
+        assert!(is_status_condition_true(&conditions, "Ready"));
+    }
+
+    #[test]
+    fn conditions_should_be_deduplicated_by_type() {
+        let mut conditions = Vec::new();
+        set_status_condition(
+            &mut conditions,
+            condition("Ready", ConditionStatus::True, "Ok", 1),
+        );
+        set_status_condition(
+            &mut conditions,
+            condition("Degraded", ConditionStatus::False, "Ok", 1),
+        );
+        set_status_condition(
+            &mut conditions,
+            condition("Ready", ConditionStatus::False, "Broken", 2),
+        );
+        assert_eq!(conditions.len(), 2);
+        assert_eq!(
+            find_status_condition(&conditions, "Ready").map(|cond| cond.reason.as_str()),
+            Some("Broken")
+        );
+        assert!(remove_status_condition(&mut conditions, "Ready"));
+        assert!(!remove_status_condition(&mut conditions, "Ready"));
+        assert!(find_status_condition(&conditions, "Ready").is_none());
+        assert!(!is_status_condition_true(&conditions, "Ready"));
+    }
+
+    #[test]
+    fn condition_should_record_observed_generation() {
+        let meta = ObjectMeta {
+            generation: Some(4),
+            ..ObjectMeta::default()
+        };
+        let cond = Condition::new("Ready", ConditionStatus::True, "Ok", "").observing(&meta);
+        assert_eq!(cond.observed_generation, Some(4));
+    }
+
+    #[test]
+    fn condition_should_serialize_like_metav1() {
+        let mut cond = condition("Ready", ConditionStatus::True, "Ok", 0);
+        cond.observed_generation = Some(3);
+        assert_eq!(
+            serde_json::to_value(&cond).unwrap(),
+            serde_json::json!({
+                "type": "Ready",
+                "status": "True",
+                "observedGeneration": 3,
+                "lastTransitionTime": "1970-01-01T00:00:00Z",
+                "reason": "Ok",
+                "message": "",
+            })
+        );
+    }
+
+    #[cfg(feature = "fake")]
+    #[tokio::test]
+    async fn patch_status_condition_should_update_conditions_in_place() {
+        use crate::{
+            api::{Api, ApiResource, DynamicObject, GroupVersionKind, PatchParams},
+            fake::FakeApiServer,
+        };
+
+        let server = FakeApiServer::new();
+        let foo = ApiResource::from_gvk(&GroupVersionKind::gvk("clux.dev", "v1", "Foo"));
+        server.insert_with(&DynamicObject::new("foo", &foo).within("ns"), &foo);
+        let foos: Api<DynamicObject> = Api::namespaced_with(server.client(), "ns", &foo);
+        let pp = PatchParams::default();
+        let ready = Condition::new("Ready", ConditionStatus::True, "Ok", "");
+        let first = foos
+            .patch_status_condition("foo", &pp, ready.clone())
+            .await
+            .unwrap();
+        // Setting the same condition again is a no-op
+        let second = foos.patch_status_condition("foo", &pp, ready).await.unwrap();
+        assert_eq!(first.metadata.resource_version, second.metadata.resource_version);
+        let conditions: Vec<Condition> =
+            serde_json::from_value(second.data["status"]["conditions"].clone()).unwrap();
+        assert_eq!(conditions.len(), 1);
+        assert_eq!(conditions[0].type_, "Ready");
+        assert!(second.metadata.generation.is_some());
+        assert_eq!(conditions[0].observed_generation, second.metadata.generation);
+    }
+}
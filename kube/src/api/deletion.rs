@@ -0,0 +1,220 @@
+//! Deleting objects and waiting until they are actually gone
+use futures::{StreamExt, TryStreamExt};
+use serde::de::DeserializeOwned;
+use std::{collections::HashMap, convert::TryFrom, fmt::Debug, time::Duration};
+use tracing::instrument;
+
+use crate::{
+    api::{Api, DeleteParams, ListParams, Resource, WatchEvent, WatchParams},
+    error::PendingDeletion,
+    Error, Result,
+};
+
+impl<K> Api<K>
+where
+    K: Resource + Clone + DeserializeOwned + Debug,
+{
+    /// Delete a named resource, and wait until it has been removed
+    ///
+    /// Unlike [`Api::delete`], this only returns once the object is actually gone, which may take a while if it
+    /// has finalizers or is terminated gracefully. The object is tracked by its `uid`, so this returns even if a
+    /// new object with the same name is created in the meantime.
+    ///
+    /// Nothing is awaited for dry runs.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`Error::DeletionTimeout`] (listing the blocking finalizers) if the object still exists after
+    /// `timeout`, and with a `404 Not Found` if it did not exist to begin with.
+    ///
+    /// ```no_run
+    /// use kube::{api::{Api, DeleteParams}, Client};
+    /// use k8s_openapi::api::core::v1::Namespace;
+    /// use std::time::Duration;
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), kube::Error> {
+    ///     let client = Client::try_default().await?;
+    ///     let namespaces: Api<Namespace> = Api::all(client);
+    ///     namespaces.delete_and_wait("scratch", &DeleteParams::default(), Duration::from_secs(60)).await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    #[instrument(skip(self), level = "trace")]
+    pub async fn delete_and_wait(&self, name: &str, dp: &DeleteParams, timeout: Duration) -> Result<()> {
+        let deleted = self.delete(name, dp).await?;
+        if dp.dry_run {
+            return Ok(());
+        }
+        let lp = ListParams::default().fields(&format!("metadata.name={}", name));
+        let objects = match deleted.left().filter(|obj| obj.meta().uid.is_some()) {
+            Some(obj) => vec![obj],
+            // The object was returned without a uid, or not at all, so wait for whatever is left under its name
+            None => self.deleting(&lp).await?,
+        };
+        self.wait_for_deletion(&lp, objects, timeout).await
+    }
+
+    /// Delete a collection of resources, and wait until they have all been removed
+    ///
+    /// This works like [`Api::delete_and_wait`], for every object that is deleted by [`Api::delete_collection`].
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`Error::DeletionTimeout`] (listing the blocking finalizers of every remaining object) if
+    /// any of the objects still exist after `timeout`.
+    #[instrument(skip(self), level = "trace")]
+    pub async fn delete_collection_and_wait(
+        &self,
+        dp: &DeleteParams,
+        lp: &ListParams,
+        timeout: Duration,
+    ) -> Result<()> {
+        let deleted = self.delete_collection(dp, lp).await?;
+        if dp.dry_run {
+            return Ok(());
+        }
+        let objects = match deleted.left() {
+            Some(list) => list.items,
+            None => self.deleting(lp).await?,
+        };
+        self.wait_for_deletion(lp, objects, timeout).await
+    }
+
+    /// The objects matching `lp` that are being deleted
+    async fn deleting(&self, lp: &ListParams) -> Result<Vec<K>> {
+        let mut objects = self.list(lp).await?.items;
+        objects.retain(|obj| obj.meta().deletion_timestamp.is_some());
+        Ok(objects)
+    }
+
+    /// Waits until none of `objects` (by uid) are listed by `lp` anymore
+    async fn wait_for_deletion(&self, lp: &ListParams, objects: Vec<K>, timeout: Duration) -> Result<()> {
+        // The last seen state of every object that has not been deleted yet
+        let mut pending = objects
+            .into_iter()
+            .filter_map(|obj| Some((obj.meta().uid.clone()?, obj)))
+            .collect::<HashMap<_, _>>();
+        let wp = WatchParams::try_from(lp)?;
+        let wait = async {
+            loop {
+                let list = self.list(lp).await?;
+                let mut remaining = HashMap::new();
+                for obj in list.items {
+                    if let Some(uid) = obj.meta().uid.clone() {
+                        if pending.contains_key(&uid) {
+                            remaining.insert(uid, obj);
+                        }
+                    }
+                }
+                pending = remaining;
+                if pending.is_empty() {
+                    return Ok(());
+                }
+                let version = list.metadata.resource_version.unwrap_or_default();
+                let mut events = self.watch(&wp, &version).await?.boxed();
+                while let Some(event) = events.try_next().await? {
+                    match event {
+                        WatchEvent::Added(obj) | WatchEvent::Modified(obj) => {
+                            if let Some(last) = obj.meta().uid.as_ref().and_then(|uid| pending.get_mut(uid)) {
+                                *last = obj;
+                            }
+                        }
+                        WatchEvent::Deleted(obj) => {
+                            if let Some(uid) = &obj.meta().uid {
+                                pending.remove(uid);
+                            }
+                            if pending.is_empty() {
+                                return Ok(());
+                            }
+                        }
+                        WatchEvent::Bookmark(_) => {}
+                        // Desynced, so start over from a fresh list
+                        WatchEvent::Error(err) if err.code == 410 => break,
+                        WatchEvent::Error(err) => return Err(Error::Api(err)),
+                    }
+                }
+            }
+        };
+        match tokio::time::timeout(timeout, wait).await {
+            Ok(res) => res,
+            Err(_) => Err(Error::DeletionTimeout(
+                pending
+                    .values()
+                    .map(|obj| {
+                        let meta = obj.meta();
+                        PendingDeletion {
+                            name: meta.name.clone().unwrap_or_default(),
+                            finalizers: meta.finalizers.clone().unwrap_or_default(),
+                        }
+                    })
+                    .collect(),
+            )),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "fake"))]
+mod tests {
+    use crate::{
+        api::{Api, DeleteParams, ListParams, Patch, PatchParams, PostParams},
+        error::PendingDeletion,
+        fake::FakeApiServer,
+        Error,
+    };
+    use k8s_openapi::api::core::v1::ConfigMap;
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn configmap(name: &str, finalizers: &[&str]) -> ConfigMap {
+        serde_json::from_value(json!({ "metadata": { "name": name, "finalizers": finalizers } })).unwrap()
+    }
+
+    #[tokio::test]
+    async fn delete_and_wait_should_wait_for_finalizers() {
+        tokio::time::pause();
+        let server = FakeApiServer::new();
+        let cms: Api<ConfigMap> = Api::namespaced(server.client(), "ns");
+        cms.create(&PostParams::default(), &configmap("a", &["test"]))
+            .await
+            .unwrap();
+        cms.create(&PostParams::default(), &configmap("b", &[]))
+            .await
+            .unwrap();
+
+        cms.delete_and_wait("b", &DeleteParams::default(), Duration::from_secs(1))
+            .await
+            .unwrap();
+        match cms
+            .delete_collection_and_wait(
+                &DeleteParams::default(),
+                &ListParams::default(),
+                Duration::from_secs(1),
+            )
+            .await
+        {
+            Err(Error::DeletionTimeout(pending)) => assert_eq!(pending, vec![PendingDeletion {
+                name: "a".into(),
+                finalizers: vec!["test".into()],
+            }]),
+            other => panic!("expected timeout, got {:?}", other),
+        }
+
+        let finalizer = {
+            let cms = cms.clone();
+            async move {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                cms.patch(
+                    "a",
+                    &PatchParams::default(),
+                    &Patch::Merge(json!({ "metadata": { "finalizers": null } })),
+                )
+                .await
+                .unwrap();
+            }
+        };
+        let dp = DeleteParams::default();
+        let (deleted, ()) = futures::join!(cms.delete_and_wait("a", &dp, Duration::from_secs(5)), finalizer);
+        deleted.unwrap();
+        assert!(cms.get_opt("a").await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,287 @@
+//! Get-or-create access to a single named object, see [`Api::entry`]
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt::Debug;
+
+use crate::{
+    api::{Api, PostParams, Resource},
+    Error, Result,
+};
+
+impl<K> Api<K>
+where
+    K: Resource + Clone + DeserializeOwned + Debug,
+{
+    /// Get a named object, or a placeholder for creating it if it does not exist yet
+    ///
+    /// Changes made through the [`Entry`] are only saved when [`OccupiedEntry::commit`] is called.
+    ///
+    /// ```no_run
+    /// use kube::{api::{Api, PostParams}, Client};
+    /// use k8s_openapi::api::core::v1::ConfigMap;
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), kube::Error> {
+    ///     let client = Client::try_default().await?;
+    ///     let cms: Api<ConfigMap> = Api::namespaced(client, "apps");
+    ///     let mut entry = cms
+    ///         .entry("settings")
+    ///         .await?
+    ///         .or_insert(ConfigMap::default)
+    ///         .and_modify(|cm| {
+    ///             cm.data.get_or_insert_with(Default::default).insert("mode".into(), "fast".into());
+    ///         });
+    ///     // Fails with a 409 Conflict (see `kube::Error::is_conflict`) if someone else changed it in the meantime
+    ///     entry.commit(&PostParams::default()).await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn entry<'a>(&'a self, name: &'a str) -> Result<Entry<'a, K>> {
+        Ok(match self.get_opt(name).await? {
+            Some(object) => Entry::Occupied(OccupiedEntry {
+                api: self,
+                name,
+                object,
+                state: EntryState::Clean,
+            }),
+            None => Entry::Vacant(VacantEntry { api: self, name }),
+        })
+    }
+}
+
+/// A view into a single named object, which may or may not exist
+pub enum Entry<'a, K> {
+    /// The object exists (or will be created when committed)
+    Occupied(OccupiedEntry<'a, K>),
+    /// The object does not exist
+    Vacant(VacantEntry<'a, K>),
+}
+
+impl<'a, K> Entry<'a, K> {
+    /// Borrow the object, if it exists
+    pub fn get(&self) -> Option<&K> {
+        match self {
+            Entry::Occupied(entry) => Some(entry.get()),
+            Entry::Vacant(_) => None,
+        }
+    }
+
+    /// Borrow the object mutably, if it exists
+    ///
+    /// The object will be replaced when the entry is committed.
+    pub fn get_mut(&mut self) -> Option<&mut K> {
+        match self {
+            Entry::Occupied(entry) => Some(entry.get_mut()),
+            Entry::Vacant(_) => None,
+        }
+    }
+
+    /// Modify the object, if it exists
+    pub fn and_modify(self, f: impl FnOnce(&mut K)) -> Self {
+        match self {
+            Entry::Occupied(entry) => Entry::Occupied(entry.and_modify(f)),
+            vacant @ Entry::Vacant(_) => vacant,
+        }
+    }
+
+    /// Use the existing object, or create it as `default()` if it does not exist
+    ///
+    /// The created object is only saved when the entry is committed.
+    pub fn or_insert(self, default: impl FnOnce() -> K) -> OccupiedEntry<'a, K>
+    where
+        K: Resource,
+    {
+        match self {
+            Entry::Occupied(entry) => entry,
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+}
+
+/// Whether an [`OccupiedEntry`] has changes that need to be saved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EntryState {
+    /// Unchanged since it was read from (or saved to) the API server
+    Clean,
+    /// Changed since it was read, and must be replaced
+    Dirty,
+    /// Doesn't exist on the API server yet, and must be created
+    New,
+}
+
+/// An object that exists, or that will be created when it is committed
+pub struct OccupiedEntry<'a, K> {
+    api: &'a Api<K>,
+    name: &'a str,
+    object: K,
+    state: EntryState,
+}
+
+impl<'a, K> OccupiedEntry<'a, K> {
+    /// Borrow the object
+    pub fn get(&self) -> &K {
+        &self.object
+    }
+
+    /// Borrow the object mutably
+    ///
+    /// The object will be replaced when the entry is committed.
+    pub fn get_mut(&mut self) -> &mut K {
+        if self.state == EntryState::Clean {
+            self.state = EntryState::Dirty;
+        }
+        &mut self.object
+    }
+
+    /// Modify the object
+    pub fn and_modify(mut self, f: impl FnOnce(&mut K)) -> Self {
+        f(self.get_mut());
+        self
+    }
+
+    /// Take ownership of the object, discarding any changes that have not been committed
+    pub fn into_object(self) -> K {
+        self.object
+    }
+}
+
+impl<'a, K> OccupiedEntry<'a, K>
+where
+    K: Resource + Clone + DeserializeOwned + Serialize + Debug,
+{
+    /// Save the object, by creating it if it is new or by replacing it if it was modified
+    ///
+    /// The object is replaced using the `resourceVersion` that it was read with, so this fails with a `409 Conflict`
+    /// if it was changed by someone else in the meantime (and with `409 AlreadyExists` if someone else created it first).
+    /// On success, the entry is updated to the object returned by the API server.
+    ///
+    /// Nothing is sent if the object has not been modified since it was read or last committed.
+    ///
+    /// If `pp` is a dry run, the entry is left unchanged, so that it can still be committed for real afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Fails without contacting the API server if the name of the object has been changed.
+    pub async fn commit(&mut self, pp: &PostParams) -> Result<()> {
+        if self.object.meta().name.as_deref() != Some(self.name) {
+            return Err(Error::RequestValidation(format!(
+                "the name of the object ({:?}) does not match the name of the entry ({})",
+                self.object.meta().name,
+                self.name
+            )));
+        }
+        let object = match self.state {
+            EntryState::Clean => return Ok(()),
+            EntryState::Dirty => self.api.replace(self.name, pp, &self.object).await?,
+            EntryState::New => self.api.create(pp, &self.object).await?,
+        };
+        if !pp.dry_run {
+            self.object = object;
+            self.state = EntryState::Clean;
+        }
+        Ok(())
+    }
+}
+
+/// An object that does not exist
+pub struct VacantEntry<'a, K> {
+    api: &'a Api<K>,
+    name: &'a str,
+}
+
+impl<'a, K> VacantEntry<'a, K> {
+    /// Use `object` as the new object, which will be created when the entry is committed
+    ///
+    /// `metadata.name` is set to the name of the entry if it is missing.
+    pub fn insert(self, mut object: K) -> OccupiedEntry<'a, K>
+    where
+        K: Resource,
+    {
+        object
+            .meta_mut()
+            .name
+            .get_or_insert_with(|| self.name.to_string());
+        OccupiedEntry {
+            api: self.api,
+            name: self.name,
+            object,
+            state: EntryState::New,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "fake"))]
+mod tests {
+    use super::Entry;
+    use crate::{
+        api::{Api, PostParams, ResourceExt},
+        fake::FakeApiServer,
+        Error,
+    };
+    use k8s_openapi::api::core::v1::ConfigMap;
+
+    #[tokio::test]
+    async fn entry_should_create_and_replace() {
+        let server = FakeApiServer::new();
+        let cms: Api<ConfigMap> = Api::namespaced(server.client(), "ns");
+        let mut entry = cms.entry("a").await.unwrap();
+        assert!(matches!(entry, Entry::Vacant(_)));
+        assert!(entry.get_mut().is_none());
+        let mut created = entry.or_insert(ConfigMap::default);
+        created.commit(&PostParams::default()).await.unwrap();
+        assert_eq!(created.get().name(), "a");
+        assert!(created.get().uid().is_some());
+
+        let mut first = cms.entry("a").await.unwrap().or_insert(|| unreachable!());
+        let mut second = cms
+            .entry("a")
+            .await
+            .unwrap()
+            .or_insert(|| unreachable!())
+            .and_modify(|cm| cm.metadata.labels = Some(vec![("x".into(), "y".into())].into_iter().collect()));
+        second.commit(&PostParams::default()).await.unwrap();
+        assert_eq!(cms.get("a").await.unwrap().labels()["x"], "y");
+        // `first` was never modified, so there is nothing to commit
+        first.commit(&PostParams::default()).await.unwrap();
+        first.get_mut().metadata.labels = None;
+        assert!(first
+            .commit(&PostParams::default())
+            .await
+            .unwrap_err()
+            .is_conflict());
+
+        let renamed = cms
+            .entry("a")
+            .await
+            .unwrap()
+            .and_modify(|cm| cm.metadata.name = Some("b".into()));
+        assert_eq!(renamed.get().unwrap().name(), "b");
+        match renamed {
+            Entry::Occupied(mut entry) => assert!(matches!(
+                entry.commit(&PostParams::default()).await,
+                Err(Error::RequestValidation(_))
+            )),
+            Entry::Vacant(_) => panic!("entry should exist"),
+        }
+    }
+
+    #[tokio::test]
+    async fn dry_run_commits_should_not_change_the_entry() {
+        let server = FakeApiServer::new();
+        let cms: Api<ConfigMap> = Api::namespaced(server.client(), "ns");
+        let mut entry = cms.entry("a").await.unwrap().or_insert(ConfigMap::default);
+        let dry_run = PostParams {
+            dry_run: true,
+            ..PostParams::default()
+        };
+        entry.commit(&dry_run).await.unwrap();
+        assert!(entry.get().uid().is_none());
+        assert!(cms.get_opt("a").await.unwrap().is_none());
+        entry.commit(&PostParams::default()).await.unwrap();
+        assert!(entry.get().uid().is_some());
+
+        entry.get_mut().metadata.labels = Some(vec![("x".into(), "y".into())].into_iter().collect());
+        entry.commit(&dry_run).await.unwrap();
+        assert!(cms.get("a").await.unwrap().metadata.labels.is_none());
+        entry.commit(&PostParams::default()).await.unwrap();
+        assert_eq!(cms.get("a").await.unwrap().labels()["x"], "y");
+    }
+}
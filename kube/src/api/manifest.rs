@@ -0,0 +1,367 @@
+//! Server-side apply of multi-document YAML manifests, like `kubectl apply -f`
+use serde::Deserialize;
+use tracing::instrument;
+
+use crate::{
+    api::{Api, ApiResource, DynamicObject, Patch, PatchParams},
+    client::discovery::{ApiResourceExtras, Discovery, Group, Scope},
+    Client, Error, Result,
+};
+
+/// A set of objects of arbitrary kinds, read from YAML manifests
+///
+/// The kind of every object is resolved through [`Discovery`] when the manifest is applied, so the manifest
+/// may contain any built-in or custom resource served by the cluster.
+///
+/// ```no_run
+/// use kube::{api::{Manifest, PatchParams}, client::discovery::Discovery, Client};
+/// #[tokio::main]
+/// async fn main() -> Result<(), kube::Error> {
+///     let client = Client::try_default().await?;
+///     let mut discovery = Discovery::new(&client).await?;
+///     let manifest = Manifest::from_yaml(&std::fs::read_to_string("deploy.yaml").unwrap())?.default_namespace("apps");
+///     for applied in manifest.apply(&client, &mut discovery, &PatchParams::apply("deployer")).await? {
+///         if let Err(err) = applied.result {
+///             eprintln!("failed to apply {:?}: {}", applied.object.metadata.name, err);
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Manifest {
+    objects: Vec<DynamicObject>,
+    namespace: String,
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Self {
+            objects: Vec::new(),
+            namespace: "default".into(),
+        }
+    }
+}
+
+/// The outcome of applying a single object of a [`Manifest`]
+#[derive(Debug)]
+pub struct AppliedObject {
+    /// The object, as it was read from the manifest
+    pub object: DynamicObject,
+    /// The object as stored by the API server, or why it could not be applied
+    pub result: Result<DynamicObject>,
+}
+
+impl Manifest {
+    /// Parse the objects of a (possibly multi-document) YAML manifest
+    ///
+    /// # Errors
+    ///
+    /// Fails if any document is not valid YAML, or is not an object with an `apiVersion`, `kind` and `metadata.name`.
+    /// Empty documents are skipped.
+    pub fn from_yaml(yaml: &str) -> Result<Self> {
+        let mut manifest = Self::default();
+        manifest.add_yaml(yaml)?;
+        Ok(manifest)
+    }
+
+    /// Parse the objects of another YAML manifest, and add them to this one
+    ///
+    /// This can be used to apply the manifests of a whole directory at once, so that they are ordered together.
+    /// Fails like [`Manifest::from_yaml`], in which case nothing is added.
+    pub fn add_yaml(&mut self, yaml: &str) -> Result<()> {
+        let mut objects = Vec::new();
+        for (i, document) in serde_yaml::Deserializer::from_str(yaml).enumerate() {
+            let value = serde_yaml::Value::deserialize(document).map_err(Error::ManifestParse)?;
+            if value.is_null() {
+                continue;
+            }
+            let object: DynamicObject = serde_yaml::from_value(value).map_err(Error::ManifestParse)?;
+            if object.types.is_none() || object.metadata.name.is_none() {
+                return Err(Error::RequestValidation(format!(
+                    "document {} of the manifest must have an apiVersion, kind and metadata.name",
+                    i
+                )));
+            }
+            objects.push(object);
+        }
+        self.objects.append(&mut objects);
+        Ok(())
+    }
+
+    /// Set the namespace of the namespaced objects that do not specify one, which is `default` by default
+    pub fn default_namespace(mut self, namespace: &str) -> Self {
+        self.namespace = namespace.into();
+        self
+    }
+
+    /// The objects of the manifest, in the order they were read
+    pub fn objects(&self) -> &[DynamicObject] {
+        &self.objects
+    }
+
+    /// The objects of the manifest, in the order they are applied
+    ///
+    /// Namespaces come first, then CustomResourceDefinitions, then everything else in the order it was read.
+    fn ordered(&self) -> Vec<&DynamicObject> {
+        let mut objects = self.objects.iter().collect::<Vec<_>>();
+        objects.sort_by_key(|object| match gvk_of(object) {
+            (Group::CORE_GROUP, _, "Namespace") => 0,
+            ("apiextensions.k8s.io", _, "CustomResourceDefinition") => 1,
+            _ => 2,
+        });
+        objects
+    }
+
+    /// Server-side apply every object of the manifest
+    ///
+    /// Objects are applied one at a time, Namespaces and CustomResourceDefinitions first, and the returned
+    /// results are in that order. A failure to apply one object does not stop the others from being applied.
+    /// Objects whose `apiVersion` and `kind` are not served by the cluster fail with [`Error::RequestValidation`].
+    ///
+    /// Namespaced objects without a namespace are applied to the [default namespace](Manifest::default_namespace),
+    /// and the namespace of cluster-scoped objects is ignored.
+    ///
+    /// If a custom resource can't be resolved after CustomResourceDefinitions were applied, `discovery` is
+    /// refreshed once. Custom resources whose definition is not established by then can be applied by applying
+    /// the manifest again.
+    ///
+    /// # Errors
+    ///
+    /// Fails without applying anything if `pp` has no field manager, and fails if refreshing `discovery` fails.
+    #[instrument(skip(self, client, discovery), level = "trace")]
+    pub async fn apply(
+        &self,
+        client: &Client,
+        discovery: &mut Discovery,
+        pp: &PatchParams,
+    ) -> Result<Vec<AppliedObject>> {
+        if pp.field_manager.is_none() {
+            return Err(Error::RequestValidation(
+                "server-side apply requires a PatchParams::field_manager".into(),
+            ));
+        }
+        let mut applied = Vec::new();
+        let mut applied_crds = false;
+        let mut refreshed = false;
+        for object in self.ordered() {
+            let mut resource = resolve(discovery, object);
+            if resource.is_none() && applied_crds && !refreshed {
+                *discovery = Discovery::new(client).await?;
+                refreshed = true;
+                resource = resolve(discovery, object);
+            }
+            let result = match resource {
+                Some((ar, extras)) => self.apply_object(client, &ar, &extras, object, pp).await,
+                None => {
+                    let (group, version, kind) = gvk_of(object);
+                    Err(Error::RequestValidation(format!(
+                        "kind {} is not served by the cluster in group {} version {}",
+                        kind, group, version
+                    )))
+                }
+            };
+            if result.is_ok() && !pp.dry_run {
+                applied_crds |= gvk_of(object).2 == "CustomResourceDefinition";
+            }
+            applied.push(AppliedObject {
+                object: object.clone(),
+                result,
+            });
+        }
+        Ok(applied)
+    }
+
+    async fn apply_object(
+        &self,
+        client: &Client,
+        ar: &ApiResource,
+        extras: &ApiResourceExtras,
+        object: &DynamicObject,
+        pp: &PatchParams,
+    ) -> Result<DynamicObject> {
+        let name = object.metadata.name.as_deref().unwrap_or_default();
+        match extras.scope {
+            Scope::Namespaced => {
+                let namespace = object.metadata.namespace.as_deref().unwrap_or(&self.namespace);
+                let api: Api<DynamicObject> = Api::namespaced_with(client.clone(), namespace, ar);
+                api.patch(name, pp, &Patch::Apply(object)).await
+            }
+            Scope::Cluster => {
+                let mut object = object.clone();
+                object.metadata.namespace = None;
+                let api: Api<DynamicObject> = Api::all_with(client.clone(), ar);
+                api.patch(name, pp, &Patch::Apply(&object)).await
+            }
+        }
+    }
+}
+
+/// The group, version and kind of a parsed object
+fn gvk_of(object: &DynamicObject) -> (&str, &str, &str) {
+    let types = object.types.as_ref().expect("manifest objects have types");
+    let (group, version) = Discovery::parse_api_version(&types.api_version).unwrap_or_default();
+    (group, version, &types.kind)
+}
+
+fn resolve(discovery: &Discovery, object: &DynamicObject) -> Option<(ApiResource, ApiResourceExtras)> {
+    let (group, version, kind) = gvk_of(object);
+    discovery.resolve_group_version_kind(group, version, kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Manifest;
+    use crate::Error;
+
+    #[test]
+    fn manifests_should_be_parsed_and_ordered() {
+        let manifest = Manifest::from_yaml(
+            r#"
+apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: blog
+  namespace: apps
+spec:
+  replicas: 2
+---
+# only a comment
+---
+apiVersion: apiextensions.k8s.io/v1
+kind: CustomResourceDefinition
+metadata:
+  name: posts.blog.example.com
+---
+apiVersion: v1
+kind: Namespace
+metadata:
+  name: apps
+---
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: settings
+"#,
+        )
+        .unwrap();
+        let names = |objects: Vec<_>| {
+            objects
+                .into_iter()
+                .map(|object: &crate::api::DynamicObject| object.metadata.name.clone().unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(manifest.objects().iter().collect()), vec![
+            "blog",
+            "posts.blog.example.com",
+            "apps",
+            "settings"
+        ]);
+        assert_eq!(names(manifest.ordered()), vec![
+            "apps",
+            "posts.blog.example.com",
+            "blog",
+            "settings"
+        ]);
+        assert_eq!(manifest.objects()[0].data["spec"]["replicas"], 2);
+    }
+
+    #[test]
+    fn invalid_manifests_should_be_rejected() {
+        let mut manifest =
+            Manifest::from_yaml("apiVersion: v1\nkind: Namespace\nmetadata:\n  name: apps\n").unwrap();
+        assert!(matches!(
+            manifest.add_yaml("apiVersion: v1\nkind: Namespace\nmetadata:\n  name: ok\n---\nkind: Namespace\nmetadata:\n  name: x\n"),
+            Err(Error::RequestValidation(_))
+        ));
+        assert!(matches!(
+            manifest.add_yaml("apiVersion: v1\nkind: Namespace\nmetadata: [\n"),
+            Err(Error::ManifestParse(_))
+        ));
+        assert!(matches!(
+            manifest.add_yaml("apiVersion: v1\nkind: ConfigMap\nmetadata:\n  generateName: x-\n"),
+            Err(Error::RequestValidation(_))
+        ));
+        assert_eq!(manifest.objects().len(), 1);
+    }
+}
+
+#[cfg(all(test, feature = "fake"))]
+mod fake_tests {
+    use super::Manifest;
+    use crate::{
+        api::{Api, PatchParams, ResourceExt},
+        client::discovery::Discovery,
+        fake::FakeApiServer,
+        Error,
+    };
+    use k8s_openapi::api::core::v1::{ConfigMap, Namespace};
+    use serde_json::json;
+
+
+    #[tokio::test]
+    async fn manifests_should_be_applied_by_scope() {
+        let server = FakeApiServer::new();
+        let client = server.client();
+        let mut discovery = Discovery::from_resource_lists(vec![
+            serde_json::from_value(json!({
+                "groupVersion": "v1",
+                "resources": [
+                    { "name": "configmaps", "singularName": "", "namespaced": true, "kind": "ConfigMap", "verbs": ["patch"] },
+                    { "name": "namespaces", "singularName": "", "namespaced": false, "kind": "Namespace", "verbs": ["patch"] },
+                ],
+            }))
+            .unwrap(),
+        ]);
+        let manifest = Manifest::from_yaml(
+            r#"
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: a
+data:
+  key: value
+---
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: b
+  namespace: other
+---
+apiVersion: blog.example.com/v1
+kind: Post
+metadata:
+  name: hello
+---
+apiVersion: v1
+kind: Namespace
+metadata:
+  name: apps
+  namespace: ignored
+"#,
+        )
+        .unwrap()
+        .default_namespace("apps");
+        assert!(manifest
+            .apply(&client, &mut discovery, &PatchParams::default())
+            .await
+            .is_err());
+        let applied = manifest
+            .apply(&client, &mut discovery, &PatchParams::apply("test"))
+            .await
+            .unwrap();
+        let names = applied
+            .iter()
+            .map(|applied| applied.object.name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["apps", "a", "b", "hello"]);
+        assert!(matches!(applied[3].result, Err(Error::RequestValidation(_))));
+
+        let namespaces: Api<Namespace> = Api::all(client.clone());
+        assert_eq!(namespaces.get("apps").await.unwrap().metadata.namespace, None);
+        let cms: Api<ConfigMap> = Api::namespaced(client.clone(), "apps");
+        assert_eq!(cms.get("a").await.unwrap().data.unwrap()["key"], "value");
+        let cms: Api<ConfigMap> = Api::namespaced(client, "other");
+        assert!(cms.get_opt("b").await.unwrap().is_some());
+    }
+}
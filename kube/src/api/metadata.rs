@@ -79,6 +79,33 @@ pub trait Resource {
     fn meta(&self) -> &ObjectMeta;
     /// Metadata that all persisted resources must have
     fn meta_mut(&mut self) -> &mut ObjectMeta;
+
+    /// Generates an owner reference pointing to this resource
+    ///
+    /// Returns `None` if the name or uid is missing, which is the case until the
+    /// resource has been created by the apiserver.
+    fn owner_ref(&self, dt: &Self::DynamicType) -> Option<OwnerReference> {
+        let meta = self.meta();
+        Some(OwnerReference {
+            api_version: Self::api_version(dt).into_owned(),
+            kind: Self::kind(dt).into_owned(),
+            name: meta.name.clone()?,
+            uid: meta.uid.clone()?,
+            ..OwnerReference::default()
+        })
+    }
+
+    /// Generates a controller owner reference pointing to this resource
+    ///
+    /// This is an [`owner_ref`](Resource::owner_ref) with `controller` and `blockOwnerDeletion` set,
+    /// which is what a controller should attach to the children that it creates.
+    fn controller_owner_ref(&self, dt: &Self::DynamicType) -> Option<OwnerReference> {
+        Some(OwnerReference {
+            controller: Some(true),
+            block_owner_deletion: Some(true),
+            ..self.owner_ref(dt)?
+        })
+    }
 }
 
 /// Helper methods for resources.
@@ -115,6 +142,16 @@ pub trait ResourceExt: Resource {
     fn finalizers(&self) -> &[String];
     /// Provides mutable access to the finalizers
     fn finalizers_mut(&mut self) -> &mut Vec<String>;
+    /// Returns the owner reference that points to the controller of this resource, if any
+    fn controller_ref(&self) -> Option<&OwnerReference>;
+    /// Whether `owner` is one of the owners of this resource
+    ///
+    /// Owners are compared by uid, so this is always `false` if `owner` has no uid.
+    fn is_owned_by<O: Resource>(&self, owner: &O) -> bool;
+    /// Whether `owner` is the controller of this resource
+    ///
+    /// Owners are compared by uid, so this is always `false` if `owner` has no uid.
+    fn is_controlled_by<O: Resource>(&self, owner: &O) -> bool;
 }
 
 // TODO: replace with ordinary static when BTreeMap::new() is no longer
@@ -169,6 +206,27 @@ impl<K: Resource> ResourceExt for K {
     fn finalizers_mut(&mut self) -> &mut Vec<String> {
         self.meta_mut().finalizers.get_or_insert_with(Vec::new)
     }
+
+    fn controller_ref(&self) -> Option<&OwnerReference> {
+        self.owner_references()
+            .iter()
+            .find(|owner_ref| owner_ref.controller == Some(true))
+    }
+
+    fn is_owned_by<O: Resource>(&self, owner: &O) -> bool {
+        owner.meta().uid.as_ref().map_or(false, |uid| {
+            self.owner_references()
+                .iter()
+                .any(|owner_ref| &owner_ref.uid == uid)
+        })
+    }
+
+    fn is_controlled_by<O: Resource>(&self, owner: &O) -> bool {
+        match (self.controller_ref(), &owner.meta().uid) {
+            (Some(controller), Some(uid)) => &controller.uid == uid,
+            _ => false,
+        }
+    }
 }
 
 /// Implement accessor trait for any ObjectMeta-using Kubernetes Resource
@@ -312,3 +370,34 @@ fn test_to_plural_native() {
         assert_eq!(to_plural(&kind.to_ascii_lowercase()), plural);
     }
 }
+
+#[test]
+fn test_owner_refs() {
+    use k8s_openapi::api::core::v1::{ConfigMap, Secret};
+    let mut owner = Secret::default();
+    owner.metadata.name = Some("owner".into());
+    assert_eq!(owner.owner_ref(&()), None);
+    owner.metadata.uid = Some("1234".into());
+    let owner_ref = owner.owner_ref(&()).unwrap();
+    assert_eq!(
+        (
+            owner_ref.api_version.as_str(),
+            owner_ref.kind.as_str(),
+            owner_ref.controller
+        ),
+        ("v1", "Secret", None)
+    );
+
+    let mut child = ConfigMap::default();
+    assert!(!child.is_owned_by(&owner));
+    child.owner_references_mut().push(owner_ref);
+    assert!(child.is_owned_by(&owner));
+    assert!(!child.is_controlled_by(&owner));
+    child.metadata.owner_references = Some(vec![owner.controller_owner_ref(&()).unwrap()]);
+    assert!(child.is_controlled_by(&owner));
+    assert_eq!(child.controller_ref().unwrap().block_owner_deletion, Some(true));
+    // Owners are identified by uid, not by name
+    owner.metadata.uid = Some("5678".into());
+    assert!(!child.is_owned_by(&owner));
+    assert!(!child.is_controlled_by(&owner));
+}
@@ -11,7 +11,8 @@ pub struct NotUsed {}
 
 pub(crate) mod params;
 pub use params::{
-    DeleteParams, ListParams, Patch, PatchParams, PostParams, Preconditions, PropagationPolicy,
+    ContinueExpiry, DeleteParams, ListParams, Patch, PatchParams, PostParams, Preconditions,
+    PropagationPolicy, VersionMatch, WatchParams,
 };
 mod request;
 pub use request::Request;
@@ -28,6 +29,23 @@ pub use dynamic::{ApiResource, DynamicObject};
 #[cfg(feature = "ws")] mod remote_command;
 #[cfg(feature = "ws")] pub use remote_command::AttachedProcess;
 
+mod condition;
+pub use condition::{
+    find_status_condition, is_status_condition_true, remove_status_condition, set_status_condition,
+    Condition, ConditionStatus,
+};
+
+mod selector;
+pub use selector::{Expression, Selector};
+
+mod deletion;
+
+mod manifest;
+pub use manifest::{AppliedObject, Manifest};
+
+mod entry;
+pub use entry::{Entry, OccupiedEntry, VacantEntry};
+
 mod subresource;
 #[cfg(feature = "ws")]
 pub use subresource::{AttachParams, Attachable, Executable};
@@ -3,7 +3,7 @@ use crate::{
     error::ErrorResponse,
 };
 use serde::{Deserialize, Serialize};
-use std::fmt::Debug;
+use std::{collections::BTreeMap, fmt::Debug};
 
 /// A raw event returned from a watch query
 ///
@@ -58,6 +58,10 @@ pub struct Bookmark {
 #[serde(rename_all = "camelCase")]
 pub struct BookmarkMeta {
     pub resource_version: String,
+    /// Set to `k8s.io/initial-events-end: "true"` on the bookmark that ends the initial events of a watch,
+    /// see [`WatchParams::send_initial_events`](crate::api::WatchParams::send_initial_events)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, String>,
 }
 
 // -------------------------------------------------------
@@ -1,6 +1,8 @@
 ///! A port of *Optionals from apimachinery/types.go
+use super::Selector;
 use crate::{Error, Result};
 use serde::Serialize;
+use std::convert::TryFrom;
 
 /// Common query parameters used in watch/list/delete calls on collections
 #[derive(Clone, Debug)]
@@ -45,6 +47,44 @@ pub struct ListParams {
     ///
     /// After listing results with a limit, a continue token can be used to fetch another page of results.
     pub continue_token: Option<String>,
+
+    /// The `resourceVersion` that list calls should be served at.
+    ///
+    /// How this is interpreted depends on [`ListParams::version_match`]. If unset, the list is served from the
+    /// latest state (a quorum read from etcd). `"0"` lets the API server answer from its watch cache, which is much
+    /// cheaper but may be arbitrarily stale.
+    ///
+    /// This is ignored by watch calls, which are given their version separately.
+    /// See the [Kubernetes API docs](https://kubernetes.io/docs/reference/using-api/api-concepts/#resource-versions)
+    /// for details.
+    pub resource_version: Option<String>,
+
+    /// How [`ListParams::resource_version`] is matched by list calls.
+    ///
+    /// Requires [`ListParams::resource_version`] to be set. Supported by Kubernetes 1.19 and later.
+    pub version_match: Option<VersionMatch>,
+}
+
+/// How the `resourceVersion` of a list call is matched, see [`ListParams::version_match`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VersionMatch {
+    /// Return data at least as new as the given `resourceVersion`
+    ///
+    /// The API server answers with the newest data that it has available, which may be served from its cache.
+    NotOlderThan,
+    /// Return data at exactly the given `resourceVersion`
+    ///
+    /// Fails with `410 Gone` if that version has been compacted away.
+    Exact,
+}
+
+impl VersionMatch {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            VersionMatch::NotOlderThan => "NotOlderThan",
+            VersionMatch::Exact => "Exact",
+        }
+    }
 }
 
 impl Default for ListParams {
@@ -58,20 +98,39 @@ impl Default for ListParams {
             timeout: None,
             limit: None,
             continue_token: None,
+            resource_version: None,
+            version_match: None,
         }
     }
 }
 
 impl ListParams {
+    /// Validates the parameters of a list call
+    ///
+    /// The timeout only applies to watches, so it is validated by [`WatchParams::validate`] instead.
     pub(crate) fn validate(&self) -> Result<()> {
-        if let Some(to) = &self.timeout {
-            // https://github.com/kubernetes/kubernetes/issues/6513
-            if *to >= 295 {
-                return Err(Error::RequestValidation(
-                    "ListParams::timeout must be < 295s".into(),
-                ));
+        if let Some(version_match) = self.version_match {
+            match self.resource_version.as_deref() {
+                None | Some("") => {
+                    return Err(Error::RequestValidation(
+                        "ListParams::version_match requires a resource_version".into(),
+                    ));
+                }
+                Some("0") if version_match == VersionMatch::Exact => {
+                    return Err(Error::RequestValidation(
+                        "ListParams::version_match cannot be Exact for resource_version 0".into(),
+                    ));
+                }
+                _ => {}
             }
         }
+        if self.continue_token.is_some() && (self.resource_version.is_some() || self.version_match.is_some())
+        {
+            return Err(Error::RequestValidation(
+                "ListParams::continue_token cannot be combined with a resource_version or version_match"
+                    .into(),
+            ));
+        }
         Ok(())
     }
 }
@@ -114,6 +173,20 @@ impl ListParams {
         self
     }
 
+    /// Configure the selector to restrict the list of returned objects by their labels, from a typed [`Selector`].
+    pub fn labels_from(self, selector: &Selector) -> Self {
+        self.labels(&selector.to_string())
+    }
+
+    /// Configure the selector to restrict the list of returned objects by their fields, from a typed [`Selector`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if `selector` uses set-based expressions, since field selectors only support `=` and `!=`.
+    pub fn fields_from(self, selector: &Selector) -> Result<Self> {
+        Ok(self.fields(&selector.to_field_selector()?))
+    }
+
     /// Disables watch bookmarks to simplify watch handling
     ///
     /// This is not recommended to use with production watchers as it can cause desyncs.
@@ -134,6 +207,211 @@ impl ListParams {
         self.continue_token = Some(token.to_string());
         self
     }
+
+    /// Sets the `resourceVersion` that list calls are served at.
+    pub fn at(mut self, resource_version: &str) -> Self {
+        self.resource_version = Some(resource_version.to_string());
+        self
+    }
+
+    /// Sets how the `resourceVersion` of list calls is matched.
+    pub fn matching(mut self, version_match: VersionMatch) -> Self {
+        self.version_match = Some(version_match);
+        self
+    }
+
+    /// Lets list calls be served from the API server's cache, at any `resourceVersion`.
+    ///
+    /// This is much cheaper for large collections, but the result may be stale.
+    pub fn match_any(self) -> Self {
+        self.at("0")
+    }
+}
+
+/// What [`Api::list_stream_with`](crate::Api::list_stream_with) does when its continue token expires
+///
+/// Continue tokens expire (with `410 Gone`) once the snapshot of the collection that they point into has been
+/// compacted away by the API server, which typically happens after about five minutes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContinueExpiry {
+    /// Fail the stream with the `410 Gone` error
+    Fail,
+    /// Start over from a fresh list
+    ///
+    /// Objects that were already returned will be returned again, possibly in a newer state.
+    Restart,
+}
+
+/// Common query parameters used in watch calls
+#[derive(Clone, Debug)]
+pub struct WatchParams {
+    /// A selector to restrict the returned objects by their labels.
+    ///
+    /// Defaults to everything if `None`.
+    pub label_selector: Option<String>,
+
+    /// A selector to restrict the returned objects by their fields.
+    ///
+    /// Defaults to everything if `None`.
+    pub field_selector: Option<String>,
+
+    /// Timeout for the watch call.
+    ///
+    /// This limits the duration of the call, regardless of any activity or inactivity.
+    /// If unset, we will use 290s.
+    /// We limit this to 295s due to [inherent watch limitations](https://github.com/kubernetes/kubernetes/issues/6513).
+    pub timeout: Option<u32>,
+
+    /// Enables watch events with type "BOOKMARK".
+    ///
+    /// Servers that do not implement bookmarks ignore this flag and
+    /// bookmarks are sent at the server's discretion. Clients should not
+    /// assume bookmarks are returned at any specific interval, nor may they
+    /// assume the server will send any BOOKMARK event during a session.
+    pub bookmarks: bool,
+
+    /// Starts the watch with synthetic "ADDED" events for all objects that currently exist.
+    ///
+    /// The end of the initial events is marked by a bookmark with the `k8s.io/initial-events-end` annotation,
+    /// so this requires [`WatchParams::bookmarks`]. Requires the `WatchList` feature gate on the API server.
+    pub send_initial_events: bool,
+}
+
+impl Default for WatchParams {
+    fn default() -> Self {
+        Self {
+            // bookmarks stable since 1.17, and backwards compatible
+            bookmarks: true,
+
+            label_selector: None,
+            field_selector: None,
+            timeout: None,
+            send_initial_events: false,
+        }
+    }
+}
+
+impl WatchParams {
+    pub(crate) fn validate(&self) -> Result<()> {
+        if let Some(to) = &self.timeout {
+            // https://github.com/kubernetes/kubernetes/issues/6513
+            if *to >= 295 {
+                return Err(Error::RequestValidation(
+                    "WatchParams::timeout must be < 295s".into(),
+                ));
+            }
+        }
+        if self.send_initial_events && !self.bookmarks {
+            return Err(Error::RequestValidation(
+                "WatchParams::send_initial_events requires bookmarks".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Builder interface to WatchParams
+///
+/// Usage:
+/// ```
+/// use kube::api::WatchParams;
+/// let wp = WatchParams::default()
+///     .timeout(60)
+///     .labels("kubernetes.io/lifecycle=spot");
+/// ```
+impl WatchParams {
+    /// Configure the timeout for watch calls
+    ///
+    /// This limits the duration of the call, regardless of any activity or inactivity.
+    /// Defaults to 290s
+    pub fn timeout(mut self, timeout_secs: u32) -> Self {
+        self.timeout = Some(timeout_secs);
+        self
+    }
+
+    /// Configure the selector to restrict the returned objects by their fields.
+    ///
+    /// Defaults to everything.
+    /// Supports `=`, `==`, `!=`, and can be comma separated: `key1=value1,key2=value2`.
+    /// The server only supports a limited number of field queries per type.
+    pub fn fields(mut self, field_selector: &str) -> Self {
+        self.field_selector = Some(field_selector.to_string());
+        self
+    }
+
+    /// Configure the selector to restrict the returned objects by their labels.
+    ///
+    /// Defaults to everything.
+    /// Supports `=`, `==`, `!=`, and can be comma separated: `key1=value1,key2=value2`.
+    pub fn labels(mut self, label_selector: &str) -> Self {
+        self.label_selector = Some(label_selector.to_string());
+        self
+    }
+
+    /// Configure the selector to restrict the returned objects by their labels, from a typed [`Selector`].
+    pub fn labels_from(self, selector: &Selector) -> Self {
+        self.labels(&selector.to_string())
+    }
+
+    /// Configure the selector to restrict the returned objects by their fields, from a typed [`Selector`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if `selector` uses set-based expressions, since field selectors only support `=` and `!=`.
+    pub fn fields_from(self, selector: &Selector) -> Result<Self> {
+        Ok(self.fields(&selector.to_field_selector()?))
+    }
+
+    /// Disables watch bookmarks to simplify watch handling
+    ///
+    /// This is not recommended to use with production watchers as it can cause desyncs.
+    /// See [#219](https://github.com/clux/kube-rs/issues/219) for details.
+    pub fn disable_bookmarks(mut self) -> Self {
+        self.bookmarks = false;
+        self
+    }
+
+    /// Start the watch with the current state of all objects, see [`WatchParams::send_initial_events`]
+    pub fn initial_events(mut self) -> Self {
+        self.send_initial_events = true;
+        self
+    }
+}
+
+/// Converts the watch-related parts of [`ListParams`]
+///
+/// The `resource_version` and `version_match` of the list are ignored, since a watch starts from its own version.
+/// Fails if a `limit` or `continue_token` is set, since a watch can't be paginated.
+impl TryFrom<&ListParams> for WatchParams {
+    type Error = Error;
+
+    fn try_from(lp: &ListParams) -> Result<Self> {
+        if lp.limit.is_some() {
+            return Err(Error::RequestValidation(
+                "ListParams::limit cannot be used with a watch.".into(),
+            ));
+        }
+        if lp.continue_token.is_some() {
+            return Err(Error::RequestValidation(
+                "ListParams::continue_token cannot be used with a watch.".into(),
+            ));
+        }
+        Ok(Self {
+            label_selector: lp.label_selector.clone(),
+            field_selector: lp.field_selector.clone(),
+            timeout: lp.timeout,
+            bookmarks: lp.bookmarks,
+            send_initial_events: false,
+        })
+    }
+}
+
+impl TryFrom<ListParams> for WatchParams {
+    type Error = Error;
+
+    fn try_from(lp: ListParams) -> Result<Self> {
+        Self::try_from(&lp)
+    }
 }
 
 /// Common query parameters for put/post calls
@@ -158,6 +436,23 @@ impl PostParams {
         }
         Ok(())
     }
+
+    pub(crate) fn populate_qp(&self, qp: &mut url::form_urlencoded::Serializer<String>) {
+        if self.dry_run {
+            qp.append_pair("dryRun", "All");
+        }
+        if let Some(ref field_manager) = self.field_manager {
+            qp.append_pair("fieldManager", &field_manager);
+        }
+    }
+
+    /// Perform a dryRun only
+    ///
+    /// The request is validated and admitted as usual, and the result is returned, but nothing is persisted.
+    pub fn dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
 }
 
 /// Describes changes that should be applied to a resource
@@ -354,6 +649,23 @@ pub struct DeleteParams {
     pub preconditions: Option<Preconditions>,
 }
 
+impl DeleteParams {
+    pub(crate) fn populate_qp(&self, qp: &mut url::form_urlencoded::Serializer<String>) {
+        // Also sent in the body, but the query parameter is honored by all API server versions
+        if self.dry_run {
+            qp.append_pair("dryRun", "All");
+        }
+    }
+
+    /// Perform a dryRun only
+    ///
+    /// The deletion is validated and admitted as usual, but nothing is deleted.
+    pub fn dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+}
+
 // dryRun serialization differ when used as body parameters and query strings:
 // query strings are either true/false
 // body params allow only: missing field, or ["All"]
@@ -1,4 +1,4 @@
-use super::params::{DeleteParams, ListParams, Patch, PatchParams, PostParams};
+use super::params::{DeleteParams, ListParams, Patch, PatchParams, PostParams, WatchParams};
 use crate::{Error, Result};
 
 /// A Kubernetes request builder
@@ -28,6 +28,7 @@ impl Request {
     pub fn list(&self, lp: &ListParams) -> Result<http::Request<Vec<u8>>> {
         let target = format!("{}?", self.url_path);
         let mut qp = url::form_urlencoded::Serializer::new(target);
+        lp.validate()?;
 
         if let Some(fields) = &lp.field_selector {
             qp.append_pair("fieldSelector", &fields);
@@ -41,6 +42,12 @@ impl Request {
         if let Some(continue_token) = &lp.continue_token {
             qp.append_pair("continue", continue_token);
         }
+        if let Some(resource_version) = &lp.resource_version {
+            qp.append_pair("resourceVersion", resource_version);
+        }
+        if let Some(version_match) = lp.version_match {
+            qp.append_pair("resourceVersionMatch", version_match.as_str());
+        }
 
         let urlstr = qp.finish();
         let req = http::Request::get(urlstr);
@@ -48,35 +55,29 @@ impl Request {
     }
 
     /// Watch a resource at a given version
-    pub fn watch(&self, lp: &ListParams, ver: &str) -> Result<http::Request<Vec<u8>>> {
+    pub fn watch(&self, wp: &WatchParams, ver: &str) -> Result<http::Request<Vec<u8>>> {
         let target = format!("{}?", self.url_path);
         let mut qp = url::form_urlencoded::Serializer::new(target);
-        lp.validate()?;
-        if lp.limit.is_some() {
-            return Err(Error::RequestValidation(
-                "ListParams::limit cannot be used with a watch.".into(),
-            ));
-        }
-        if lp.continue_token.is_some() {
-            return Err(Error::RequestValidation(
-                "ListParams::continue_token cannot be used with a watch.".into(),
-            ));
-        }
+        wp.validate()?;
 
         qp.append_pair("watch", "true");
         qp.append_pair("resourceVersion", ver);
 
         // https://github.com/kubernetes/kubernetes/issues/6513
-        qp.append_pair("timeoutSeconds", &lp.timeout.unwrap_or(290).to_string());
-        if let Some(fields) = &lp.field_selector {
+        qp.append_pair("timeoutSeconds", &wp.timeout.unwrap_or(290).to_string());
+        if let Some(fields) = &wp.field_selector {
             qp.append_pair("fieldSelector", &fields);
         }
-        if let Some(labels) = &lp.label_selector {
+        if let Some(labels) = &wp.label_selector {
             qp.append_pair("labelSelector", &labels);
         }
-        if lp.bookmarks {
+        if wp.bookmarks {
             qp.append_pair("allowWatchBookmarks", "true");
         }
+        if wp.send_initial_events {
+            qp.append_pair("sendInitialEvents", "true");
+            qp.append_pair("resourceVersionMatch", "NotOlderThan");
+        }
 
         let urlstr = qp.finish();
         let req = http::Request::get(urlstr);
@@ -97,9 +98,7 @@ impl Request {
         pp.validate()?;
         let target = format!("{}?", self.url_path);
         let mut qp = url::form_urlencoded::Serializer::new(target);
-        if pp.dry_run {
-            qp.append_pair("dryRun", "All");
-        }
+        pp.populate_qp(&mut qp);
         let urlstr = qp.finish();
         let req = http::Request::post(urlstr);
         req.body(data).map_err(Error::HttpError)
@@ -109,6 +108,7 @@ impl Request {
     pub fn delete(&self, name: &str, dp: &DeleteParams) -> Result<http::Request<Vec<u8>>> {
         let target = format!("{}/{}?", self.url_path, name);
         let mut qp = url::form_urlencoded::Serializer::new(target);
+        dp.populate_qp(&mut qp);
         let urlstr = qp.finish();
         let body = serde_json::to_vec(&dp)?;
         let req = http::Request::delete(urlstr);
@@ -125,6 +125,7 @@ impl Request {
         if let Some(labels) = &lp.label_selector {
             qp.append_pair("labelSelector", &labels);
         }
+        dp.populate_qp(&mut qp);
         let urlstr = qp.finish();
         let body = serde_json::to_vec(&dp)?;
         let req = http::Request::delete(urlstr);
@@ -157,11 +158,10 @@ impl Request {
     ///
     /// Requires `metadata.resourceVersion` set in data
     pub fn replace(&self, name: &str, pp: &PostParams, data: Vec<u8>) -> Result<http::Request<Vec<u8>>> {
+        pp.validate()?;
         let target = format!("{}/{}?", self.url_path, name);
         let mut qp = url::form_urlencoded::Serializer::new(target);
-        if pp.dry_run {
-            qp.append_pair("dryRun", "All");
-        }
+        pp.populate_qp(&mut qp);
         let urlstr = qp.finish();
         let req = http::Request::put(urlstr);
         req.body(data).map_err(Error::HttpError)
@@ -208,11 +208,10 @@ impl Request {
         pp: &PostParams,
         data: Vec<u8>,
     ) -> Result<http::Request<Vec<u8>>> {
+        pp.validate()?;
         let target = format!("{}/{}/{}?", self.url_path, name, subresource_name);
         let mut qp = url::form_urlencoded::Serializer::new(target);
-        if pp.dry_run {
-            qp.append_pair("dryRun", "All");
-        }
+        pp.populate_qp(&mut qp);
         let urlstr = qp.finish();
         let req = http::Request::put(urlstr);
         req.body(data).map_err(Error::HttpError)
@@ -341,8 +340,9 @@ mod test {
 
     /// -----------------------------------------------------------------
     /// Tests that the misc mappings are also sensible
-    use crate::api::{DeleteParams, ListParams, Patch, PatchParams};
+    use crate::api::{DeleteParams, ListParams, Patch, PatchParams, VersionMatch, WatchParams};
     use k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1beta1 as apiextsv1beta1;
+    use std::convert::TryFrom;
 
     #[test]
     fn list_path() {
@@ -352,10 +352,69 @@ mod test {
         assert_eq!(req.uri(), "/apis/apps/v1/namespaces/ns/deployments");
     }
     #[test]
+    fn list_at_version() {
+        let url = corev1::Pod::url_path(&(), Some("ns"));
+        let lp = ListParams::default()
+            .at("123")
+            .matching(VersionMatch::NotOlderThan);
+        let req = Request::new(url).list(&lp).unwrap();
+        assert_eq!(
+            req.uri(),
+            "/api/v1/namespaces/ns/pods?&resourceVersion=123&resourceVersionMatch=NotOlderThan"
+        );
+    }
+    #[test]
+    fn list_rejects_invalid_version_match() {
+        let req = Request::new("/api/v1/pods");
+        assert!(req
+            .list(&ListParams::default().matching(VersionMatch::Exact))
+            .is_err());
+        assert!(req
+            .list(&ListParams::default().match_any().matching(VersionMatch::Exact))
+            .is_err());
+        assert!(req
+            .list(&ListParams::default().match_any().continue_token("abc"))
+            .is_err());
+        assert!(req.list(&ListParams::default().match_any().limit(10)).is_ok());
+        // The watch timeout limit does not apply to lists
+        assert!(req.list(&ListParams::default().timeout(300)).is_ok());
+    }
+    #[test]
+    fn watch_ignores_list_version() {
+        let url = corev1::Pod::url_path(&(), Some("ns"));
+        let wp = WatchParams::try_from(ListParams::default().match_any()).unwrap();
+        let req = Request::new(url).watch(&wp, "42").unwrap();
+        assert_eq!(
+            req.uri(),
+            "/api/v1/namespaces/ns/pods?&watch=true&resourceVersion=42&timeoutSeconds=290&allowWatchBookmarks=true"
+        );
+    }
+    #[test]
+    fn watch_rejects_paginated_list_params() {
+        assert!(WatchParams::try_from(ListParams::default().limit(10)).is_err());
+        assert!(WatchParams::try_from(ListParams::default().continue_token("abc")).is_err());
+    }
+    #[test]
+    fn watch_with_initial_events() {
+        let url = corev1::Pod::url_path(&(), None);
+        let wp = WatchParams::default().labels("app=blog").initial_events();
+        let req = Request::new(url).watch(&wp, "").unwrap();
+        assert_eq!(
+            req.uri(),
+            "/api/v1/pods?&watch=true&resourceVersion=&timeoutSeconds=290&labelSelector=app%3Dblog&allowWatchBookmarks=true&sendInitialEvents=true&resourceVersionMatch=NotOlderThan"
+        );
+        assert!(Request::new("/api/v1/pods")
+            .watch(&wp.clone().disable_bookmarks(), "")
+            .is_err());
+        assert!(Request::new("/api/v1/pods")
+            .watch(&WatchParams::default().timeout(295), "0")
+            .is_err());
+    }
+    #[test]
     fn watch_path() {
         let url = corev1::Pod::url_path(&(), Some("ns"));
-        let gp = ListParams::default();
-        let req = Request::new(url).watch(&gp, "0").unwrap();
+        let wp = WatchParams::default();
+        let req = Request::new(url).watch(&wp, "0").unwrap();
         assert_eq!(
             req.uri(),
             "/api/v1/namespaces/ns/pods?&watch=true&resourceVersion=0&timeoutSeconds=290&allowWatchBookmarks=true"
@@ -381,6 +440,44 @@ mod test {
         assert_eq!(req.method(), "DELETE")
     }
 
+    #[test]
+    fn dry_run_everywhere() {
+        let req = Request::new(corev1::Pod::url_path(&(), Some("ns")));
+        let pp = PostParams {
+            field_manager: Some("app".into()),
+            ..PostParams::default()
+        }
+        .dry_run();
+        assert_eq!(
+            req.create(&pp, vec![]).unwrap().uri(),
+            "/api/v1/namespaces/ns/pods?&dryRun=All&fieldManager=app"
+        );
+        assert_eq!(
+            req.replace("p", &pp, vec![]).unwrap().uri(),
+            "/api/v1/namespaces/ns/pods/p?&dryRun=All&fieldManager=app"
+        );
+        assert_eq!(
+            req.replace_subresource("status", "p", &pp, vec![]).unwrap().uri(),
+            "/api/v1/namespaces/ns/pods/p/status?&dryRun=All&fieldManager=app"
+        );
+        let patch = Patch::Merge(());
+        assert_eq!(
+            req.patch_subresource("scale", "p", &PatchParams::default().dry_run(), &patch)
+                .unwrap()
+                .uri(),
+            "/api/v1/namespaces/ns/pods/p/scale?&dryRun=All"
+        );
+        let dp = DeleteParams::default().dry_run();
+        assert_eq!(
+            req.delete("p", &dp).unwrap().uri(),
+            "/api/v1/namespaces/ns/pods/p?&dryRun=All"
+        );
+        assert_eq!(
+            req.delete_collection(&dp, &ListParams::default()).unwrap().uri(),
+            "/api/v1/namespaces/ns/pods?&dryRun=All"
+        );
+    }
+
     #[test]
     fn delete_collection_path() {
         let url = appsv1::ReplicaSet::url_path(&(), Some("ns"));
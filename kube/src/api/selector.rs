@@ -0,0 +1,257 @@
+//! Typed label and field selectors, which can be sent to the API server or evaluated locally
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    fmt,
+    iter::FromIterator,
+};
+
+use crate::{Error, Result};
+
+/// A single requirement of a [`Selector`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Expression {
+    /// The key has one of the values
+    In(String, BTreeSet<String>),
+    /// The key is missing, or has none of the values
+    NotIn(String, BTreeSet<String>),
+    /// The key has the value
+    Equal(String, String),
+    /// The key is missing, or has a different value
+    NotEqual(String, String),
+    /// The key is present, with any value
+    Exists(String),
+    /// The key is missing
+    DoesNotExist(String),
+}
+
+impl Expression {
+    /// Whether `values` (such as the labels of an object) meet the requirement
+    pub fn matches(&self, values: &BTreeMap<String, String>) -> bool {
+        match self {
+            Expression::In(key, set) => values.get(key).map_or(false, |value| set.contains(value)),
+            Expression::NotIn(key, set) => values.get(key).map_or(true, |value| !set.contains(value)),
+            Expression::Equal(key, expected) => values.get(key) == Some(expected),
+            Expression::NotEqual(key, expected) => values.get(key) != Some(expected),
+            Expression::Exists(key) => values.contains_key(key),
+            Expression::DoesNotExist(key) => !values.contains_key(key),
+        }
+    }
+
+    /// Whether the API server supports this expression in field selectors
+    fn is_equality(&self) -> bool {
+        matches!(self, Expression::Equal(..) | Expression::NotEqual(..))
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |set: &BTreeSet<String>| set.iter().map(String::as_str).collect::<Vec<_>>().join(",");
+        match self {
+            Expression::In(key, set) => write!(f, "{} in ({})", key, join(set)),
+            Expression::NotIn(key, set) => write!(f, "{} notin ({})", key, join(set)),
+            Expression::Equal(key, value) => write!(f, "{}={}", key, value),
+            Expression::NotEqual(key, value) => write!(f, "{}!={}", key, value),
+            Expression::Exists(key) => write!(f, "{}", key),
+            Expression::DoesNotExist(key) => write!(f, "!{}", key),
+        }
+    }
+}
+
+/// A set of [`Expression`]s that must all be met
+///
+/// Selectors render into the syntax expected by [`ListParams::labels_from`](crate::api::ListParams::labels_from)
+/// and [`ListParams::fields_from`](crate::api::ListParams::fields_from), and can also be evaluated locally,
+/// for example to filter the objects of a cache:
+///
+/// ```
+/// use kube::api::{Expression, ListParams, Selector};
+/// use std::collections::BTreeMap;
+///
+/// let selector: Selector = vec![
+///     Expression::Equal("app".into(), "blog".into()),
+///     Expression::In("tier".into(), vec!["web".into(), "db".into()].into_iter().collect()),
+/// ]
+/// .into_iter()
+/// .collect();
+/// assert_eq!(selector.to_string(), "app=blog,tier in (db,web)");
+/// let lp = ListParams::default().labels_from(&selector);
+///
+/// let labels: BTreeMap<String, String> = vec![("app".into(), "blog".into()), ("tier".into(), "web".into())]
+///     .into_iter()
+///     .collect();
+/// assert!(selector.matches(&labels));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Selector(Vec<Expression>);
+
+impl Selector {
+    /// Add another requirement
+    pub fn and(mut self, expression: Expression) -> Self {
+        self.0.push(expression);
+        self
+    }
+
+    /// The requirements of the selector
+    pub fn expressions(&self) -> &[Expression] {
+        &self.0
+    }
+
+    /// Whether the selector has no requirements, and so selects everything
+    pub fn selects_all(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Whether `values` (such as the labels of an object) meet all requirements
+    pub fn matches(&self, values: &BTreeMap<String, String>) -> bool {
+        self.0.iter().all(|expression| expression.matches(values))
+    }
+
+    /// Render the selector for use as a field selector
+    ///
+    /// # Errors
+    ///
+    /// Fails if the selector uses set-based expressions, which the API server only supports for labels.
+    pub(crate) fn to_field_selector(&self) -> Result<String> {
+        match self.0.iter().find(|expression| !expression.is_equality()) {
+            Some(expression) => Err(Error::RequestValidation(format!(
+                "field selectors only support = and !=, not `{}`",
+                expression
+            ))),
+            None => Ok(self.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, expression) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            expression.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<Expression> for Selector {
+    fn from(expression: Expression) -> Self {
+        Self(vec![expression])
+    }
+}
+
+impl FromIterator<Expression> for Selector {
+    fn from_iter<T: IntoIterator<Item = Expression>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+/// Builds a selector with the same meaning as a `metav1.LabelSelector`, such as the `selector` of a `Deployment`
+///
+/// Fails if an expression has an unknown operator, or values that don't fit its operator.
+impl TryFrom<LabelSelector> for Selector {
+    type Error = Error;
+
+    fn try_from(selector: LabelSelector) -> Result<Self> {
+        let mut expressions = selector
+            .match_labels
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(key, value)| Expression::Equal(key, value))
+            .collect::<Vec<_>>();
+        for requirement in selector.match_expressions.unwrap_or_default() {
+            let key = requirement.key;
+            let values = requirement.values.unwrap_or_default();
+            let expression = match (requirement.operator.as_str(), values.is_empty()) {
+                ("In", false) => Expression::In(key, values.into_iter().collect()),
+                ("NotIn", false) => Expression::NotIn(key, values.into_iter().collect()),
+                ("Exists", true) => Expression::Exists(key),
+                ("DoesNotExist", true) => Expression::DoesNotExist(key),
+                (operator, _) => {
+                    return Err(Error::RequestValidation(format!(
+                        "invalid label selector requirement on {}: operator {} with values {:?}",
+                        key, operator, values
+                    )));
+                }
+            };
+            expressions.push(expression);
+        }
+        Ok(Self(expressions))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Expression, Selector};
+    use crate::api::ListParams;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::{LabelSelector, LabelSelectorRequirement};
+    use std::{collections::BTreeMap, convert::TryFrom};
+
+    fn labels(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn selector_should_match_like_the_api_server() {
+        let selector = Selector::try_from(LabelSelector {
+            match_labels: Some(labels(&[("app", "blog")])),
+            match_expressions: Some(vec![
+                LabelSelectorRequirement {
+                    key: "tier".into(),
+                    operator: "NotIn".into(),
+                    values: Some(vec!["db".into()]),
+                },
+                LabelSelectorRequirement {
+                    key: "canary".into(),
+                    operator: "DoesNotExist".into(),
+                    values: None,
+                },
+            ]),
+        })
+        .unwrap();
+        assert_eq!(selector.to_string(), "app=blog,tier notin (db),!canary");
+        assert!(selector.matches(&labels(&[("app", "blog")])));
+        assert!(selector.matches(&labels(&[("app", "blog"), ("tier", "web")])));
+        assert!(!selector.matches(&labels(&[("app", "blog"), ("tier", "db")])));
+        assert!(!selector.matches(&labels(&[("app", "blog"), ("canary", "")])));
+        assert!(!selector.matches(&labels(&[("tier", "web")])));
+        assert!(Selector::default().matches(&labels(&[])));
+    }
+
+    #[test]
+    fn invalid_label_selectors_should_be_rejected() {
+        let requirement = |operator: &str, values: Option<Vec<String>>| LabelSelector {
+            match_labels: None,
+            match_expressions: Some(vec![LabelSelectorRequirement {
+                key: "app".into(),
+                operator: operator.into(),
+                values,
+            }]),
+        };
+        assert!(Selector::try_from(requirement("In", None)).is_err());
+        assert!(Selector::try_from(requirement("Exists", Some(vec!["blog".into()]))).is_err());
+        assert!(Selector::try_from(requirement("Like", None)).is_err());
+    }
+
+    #[test]
+    fn field_selectors_should_only_allow_equality() {
+        let lp = ListParams::default()
+            .fields_from(
+                &Selector::from(Expression::Equal("metadata.name".into(), "a".into()))
+                    .and(Expression::NotEqual("status.phase".into(), "Running".into())),
+            )
+            .unwrap();
+        assert_eq!(
+            lp.field_selector.as_deref(),
+            Some("metadata.name=a,status.phase!=Running")
+        );
+        assert!(ListParams::default()
+            .fields_from(&Expression::Exists("metadata.name".into()).into())
+            .is_err());
+    }
+}
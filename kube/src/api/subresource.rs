@@ -5,7 +5,11 @@ use std::fmt::Debug;
 use tracing::instrument;
 
 use crate::{
-    api::{Api, DeleteParams, Patch, PatchParams, PostParams, Request},
+    api::{
+        set_status_condition,
+        typed::{modified, ConflictBackoff},
+        Api, Condition, DeleteParams, Patch, PatchParams, PostParams, Request, Resource,
+    },
     client::Status,
     Error, Result,
 };
@@ -122,6 +126,91 @@ where
         let req = self.request.replace_subresource("status", name, &pp, data)?;
         self.client.request::<K>(req).await
     }
+
+    /// Update the status of a named resource by modifying its current state
+    ///
+    /// This works like [`Api::update_with`], but reads and replaces the status subresource, so
+    /// changes to anything but `.status` are ignored.
+    ///
+    /// ```no_run
+    /// use kube::{api::{Api, PostParams}, Client};
+    /// use k8s_openapi::api::batch::v1::Job;
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), kube::Error> {
+    ///     let client = Client::try_default().await?;
+    ///     let jobs: Api<Job> = Api::namespaced(client, "apps");
+    ///     jobs.update_status_with("baz", &PostParams::default(), |j| {
+    ///         j.status.get_or_insert_with(Default::default).succeeded = Some(2);
+    ///     }).await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    #[instrument(skip(self, update), level = "trace")]
+    pub async fn update_status_with<F>(&self, name: &str, pp: &PostParams, mut update: F) -> Result<K>
+    where
+        K: Resource + serde::Serialize,
+        F: FnMut(&mut K),
+    {
+        let mut backoff = ConflictBackoff::default();
+        loop {
+            let obj = modified(self.get_status(name).await?, &mut update);
+            let res = self.replace_status(name, pp, serde_json::to_vec(&obj)?).await;
+            if !backoff.retry(&res).await {
+                return res;
+            }
+        }
+    }
+
+    /// Add or update a single condition in `.status.conditions`
+    ///
+    /// The condition is merged into the existing conditions with [`set_status_condition`], so
+    /// `lastTransitionTime` only moves when the status of the condition changes.
+    /// Nothing is written if the condition is already up to date.
+    ///
+    /// If the condition has no `observed_generation`, it is set to the `metadata.generation` of the object
+    /// that was read, so that readers can tell whether the condition reflects the latest `spec`.
+    ///
+    /// The whole list of conditions is replaced using the `resourceVersion` that was read, so this fails with
+    /// a `409 Conflict` if the object was changed in the meantime. Only use this for resources whose
+    /// conditions follow the [`Condition`] schema, such as custom resources that embed [`Condition`]s in their status.
+    ///
+    /// ```no_run
+    /// use kube::{api::{Api, ApiResource, Condition, ConditionStatus, DynamicObject, GroupVersionKind, PatchParams}, Client};
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), kube::Error> {
+    ///     let client = Client::try_default().await?;
+    ///     let foo = ApiResource::from_gvk(&GroupVersionKind::gvk("clux.dev", "v1", "Foo"));
+    ///     let foos: Api<DynamicObject> = Api::namespaced_with(client, "apps", &foo);
+    ///     let ready = Condition::new("Ready", ConditionStatus::True, "AllGood", "everything is fine");
+    ///     foos.patch_status_condition("baz", &PatchParams::default(), ready).await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    #[instrument(skip(self), level = "trace")]
+    pub async fn patch_status_condition(
+        &self,
+        name: &str,
+        pp: &PatchParams,
+        mut condition: Condition,
+    ) -> Result<K> {
+        let req = self.request.get_subresource("status", name)?;
+        let obj = self.client.request::<serde_json::Value>(req).await?;
+        if condition.observed_generation.is_none() {
+            condition.observed_generation = obj["metadata"]["generation"].as_i64();
+        }
+        let mut conditions: Vec<Condition> = match &obj["status"]["conditions"] {
+            serde_json::Value::Null => Vec::new(),
+            conditions => serde_json::from_value(conditions.clone())?,
+        };
+        if !set_status_condition(&mut conditions, condition) {
+            return Ok(serde_json::from_value(obj)?);
+        }
+        let patch = serde_json::json!({
+            "metadata": { "resourceVersion": obj["metadata"]["resourceVersion"] },
+            "status": { "conditions": conditions },
+        });
+        self.patch_status(name, pp, &Patch::Merge(&patch)).await
+    }
 }
 
 // ----------------------------------------------------------------------------
@@ -255,9 +344,7 @@ impl Request {
         let pp = &ep.post_options;
         pp.validate()?;
         let mut qp = url::form_urlencoded::Serializer::new(target);
-        if pp.dry_run {
-            qp.append_pair("dryRun", "All");
-        }
+        pp.populate_qp(&mut qp);
         let urlstr = qp.finish();
         // eviction body parameters are awkward, need metadata with name
         let data = serde_json::to_vec(&serde_json::json!({
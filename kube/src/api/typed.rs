@@ -1,12 +1,13 @@
 use either::Either;
-use futures::Stream;
+use futures::{stream, Stream, TryStreamExt};
 use serde::{de::DeserializeOwned, Serialize};
-use std::{fmt::Debug, iter};
+use std::{fmt::Debug, iter, time::Duration};
 use tracing::instrument;
 
 use crate::{
     api::{
-        DeleteParams, ListParams, ObjectList, Patch, PatchParams, PostParams, Request, Resource, WatchEvent,
+        ContinueExpiry, DeleteParams, ListParams, ObjectList, Patch, PatchParams, PostParams, Request,
+        Resource, WatchEvent, WatchParams,
     },
     client::{Client, Status},
     Result,
@@ -120,6 +121,32 @@ where
         self.client.request::<K>(req).await
     }
 
+    /// Get a named resource, if it exists
+    ///
+    /// Returns `None` rather than an error if the resource does not exist.
+    ///
+    /// ```no_run
+    /// use kube::{Api, Client};
+    /// use k8s_openapi::api::core::v1::Pod;
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), kube::Error> {
+    ///     let client = Client::try_default().await?;
+    ///     let pods: Api<Pod> = Api::namespaced(client, "apps");
+    ///     if let Some(p) = pods.get_opt("blog").await? {
+    ///         println!("blog is {:?}", p.status);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    #[instrument(skip(self), level = "trace")]
+    pub async fn get_opt(&self, name: &str) -> Result<Option<K>> {
+        match self.get(name).await {
+            Ok(obj) => Ok(Some(obj)),
+            Err(err) if err.is_not_found() => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
     /// Get a list of resources
     ///
     /// You get use this to get everything, or a subset matching fields/labels, say:
@@ -144,6 +171,90 @@ where
         self.client.request::<ObjectList<K>>(req).await
     }
 
+    /// Stream all resources matching `lp`, fetching them page by page
+    ///
+    /// Pages of up to [`ListParams::limit`] objects are requested as the stream is consumed, following the
+    /// continue token of each page until the list is exhausted. If `lp` has no limit then everything is fetched
+    /// in a single request, just like [`Api::list`].
+    ///
+    /// If the continue token expires before the list is finished, the list is restarted from the first page, so
+    /// objects may be returned more than once. Use [`Api::list_stream_with`] to fail instead.
+    ///
+    /// ```no_run
+    /// use kube::{api::{Api, ListParams, ResourceExt}, Client};
+    /// use k8s_openapi::api::core::v1::Pod;
+    /// use futures::TryStreamExt;
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), kube::Error> {
+    ///     let client = Client::try_default().await?;
+    ///     let pods: Api<Pod> = Api::all(client);
+    ///     let lp = ListParams::default().limit(500);
+    ///     let mut stream = Box::pin(pods.list_stream(&lp));
+    ///     while let Some(p) = stream.try_next().await? {
+    ///         println!("Found Pod: {}", p.name());
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn list_stream(&self, lp: &ListParams) -> impl Stream<Item = Result<K>> {
+        self.list_stream_with(lp, ContinueExpiry::Restart)
+    }
+
+    /// Stream all resources matching `lp` like [`Api::list_stream`], deciding what happens if the continue token expires
+    ///
+    /// With [`ContinueExpiry::Fail`], the stream fails with a `410 Gone` error (see `kube::Error::is_gone`) instead
+    /// of restarting, so that callers that can't handle duplicates can restart on their own terms.
+    pub fn list_stream_with(
+        &self,
+        lp: &ListParams,
+        on_expiry: ContinueExpiry,
+    ) -> impl Stream<Item = Result<K>> {
+        let api = self.clone();
+        let initial = lp.clone();
+        stream::try_unfold(Some(lp.clone()), move |lp| {
+            let api = api.clone();
+            let initial = initial.clone();
+            async move {
+                let mut lp = match lp {
+                    Some(lp) => lp,
+                    None => return Ok(None),
+                };
+                loop {
+                    match api.list(&lp).await {
+                        Ok(list) => {
+                            let next =
+                                list.metadata
+                                    .continue_
+                                    .filter(|token| !token.is_empty())
+                                    .map(|token| ListParams {
+                                        continue_token: Some(token),
+                                        // The continue token already pins the version of the list
+                                        resource_version: None,
+                                        version_match: None,
+                                        ..lp
+                                    });
+                            return Ok(Some((list.items, next)));
+                        }
+                        Err(err)
+                            if err.is_gone()
+                                && lp.continue_token.is_some()
+                                && on_expiry == ContinueExpiry::Restart =>
+                        {
+                            tracing::debug!("continue token expired, restarting list");
+                            lp = ListParams {
+                                continue_token: None,
+                                ..initial.clone()
+                            };
+                        }
+                        Err(err) => return Err(err),
+                    }
+                }
+            }
+        })
+        .map_ok(|items| stream::iter(items.into_iter().map(Ok)))
+        .try_flatten()
+    }
+
     /// Create a resource
     ///
     /// This function requires a type that Serializes to `K`, which can be:
@@ -275,6 +386,41 @@ where
         self.client.request::<K>(req).await
     }
 
+    /// Preview the result of a server-side apply, without persisting anything
+    ///
+    /// This sends `data` as a [`Patch::Apply`] with `dryRun=All`, so the request goes through defaulting,
+    /// validation and admission as usual, and returns the object as it would be stored. Failures (such as
+    /// invalid fields or field manager conflicts) are returned as they would be for a real apply.
+    ///
+    /// ```no_run
+    /// use kube::{api::{Api, PatchParams}, Client};
+    /// use k8s_openapi::api::core::v1::Pod;
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), kube::Error> {
+    ///     let client = Client::try_default().await?;
+    ///     let pods: Api<Pod> = Api::namespaced(client, "apps");
+    ///     let pod = serde_json::json!({
+    ///         "apiVersion": "v1",
+    ///         "kind": "Pod",
+    ///         "metadata": { "name": "blog" },
+    ///         "spec": { "activeDeadlineSeconds": 5 }
+    ///     });
+    ///     let preview = pods.preview_apply("blog", &PatchParams::apply("myapp"), &pod).await?;
+    ///     println!("would become {:?}", preview.spec);
+    ///     Ok(())
+    /// }
+    /// ```
+    #[instrument(skip(self, data), level = "trace")]
+    pub async fn preview_apply<P: Serialize + Debug>(
+        &self,
+        name: &str,
+        pp: &PatchParams,
+        data: &P,
+    ) -> Result<K> {
+        let pp = pp.clone().dry_run();
+        self.patch(name, &pp, &Patch::Apply(data)).await
+    }
+
     /// Replace a resource entirely with a new one
     ///
     /// This is used just like [`Api::create`], but with one additional instruction:
@@ -329,29 +475,66 @@ where
         self.client.request::<K>(req).await
     }
 
+    /// Update a named resource by modifying its current state
+    ///
+    /// This fetches the resource, passes it to `update`, and replaces it using the `resourceVersion` that was
+    /// fetched. If the resource was changed by someone else in the meantime then the whole cycle is retried
+    /// (with backoff), so `update` may be called several times and should only depend on the object it is given.
+    ///
+    /// Gives up and returns the `409 Conflict` error after 5 retries.
+    ///
+    /// ```no_run
+    /// use kube::{api::{Api, PostParams}, Client};
+    /// use k8s_openapi::api::apps::v1::Deployment;
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), kube::Error> {
+    ///     let client = Client::try_default().await?;
+    ///     let deploys: Api<Deployment> = Api::namespaced(client, "apps");
+    ///     deploys.update_with("blog", &PostParams::default(), |d| {
+    ///         d.metadata.labels.get_or_insert_with(Default::default).insert("tier".into(), "web".into());
+    ///     }).await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    #[instrument(skip(self, update), level = "trace")]
+    pub async fn update_with<F>(&self, name: &str, pp: &PostParams, mut update: F) -> Result<K>
+    where
+        K: Resource + Serialize,
+        F: FnMut(&mut K),
+    {
+        let mut backoff = ConflictBackoff::default();
+        loop {
+            let obj = modified(self.get(name).await?, &mut update);
+            let res = self.replace(name, pp, &obj).await;
+            if !backoff.retry(&res).await {
+                return res;
+            }
+        }
+    }
+
     /// Watch a list of resources
     ///
     /// This returns a future that awaits the initial response,
     /// then you can stream the remaining buffered `WatchEvent` objects.
     ///
     /// Note that a `watch` call can terminate for many reasons (even before the specified
-    /// [`ListParams::timeout`] is triggered), and will have to be re-issued
+    /// [`WatchParams::timeout`] is triggered), and will have to be re-issued
     /// with the last seen resource version when or if it closes.
     ///
     /// Consider using a managed [`watcher`] to deal with automatic re-watches and error cases.
     ///
     /// ```no_run
-    /// use kube::{api::{Api, ListParams, ResourceExt, WatchEvent}, Client};
+    /// use kube::{api::{Api, ResourceExt, WatchEvent, WatchParams}, Client};
     /// use k8s_openapi::api::batch::v1::Job;
     /// use futures::{StreamExt, TryStreamExt};
     /// #[tokio::main]
     /// async fn main() -> Result<(), kube::Error> {
     ///     let client = Client::try_default().await?;
     ///     let jobs: Api<Job> = Api::namespaced(client, "apps");
-    ///     let lp = ListParams::default()
+    ///     let wp = WatchParams::default()
     ///         .fields("metadata.name=my_job")
     ///         .timeout(20); // upper bound of how long we watch for
-    ///     let mut stream = jobs.watch(&lp, "0").await?.boxed();
+    ///     let mut stream = jobs.watch(&wp, "0").await?.boxed();
     ///     while let Some(status) = stream.try_next().await? {
     ///         match status {
     ///             WatchEvent::Added(s) => println!("Added {}", s.name()),
@@ -364,21 +547,211 @@ where
     ///     Ok(())
     /// }
     /// ```
-    /// [`ListParams::timeout`]: super::ListParams::timeout
+    /// [`WatchParams::timeout`]: super::WatchParams::timeout
     /// [`watcher`]: https://docs.rs/kube_runtime/*/kube_runtime/watcher/fn.watcher.html
     #[instrument(skip(self), level = "trace")]
     pub async fn watch(
         &self,
-        lp: &ListParams,
+        wp: &WatchParams,
         version: &str,
     ) -> Result<impl Stream<Item = Result<WatchEvent<K>>>> {
-        let req = self.request.watch(&lp, &version)?;
+        let req = self.request.watch(&wp, &version)?;
         self.client.request_events::<K>(req).await
     }
 }
 
+/// Applies `update` to `obj`, keeping the `resourceVersion` that it was read with
+pub(crate) fn modified<K: Resource>(mut obj: K, update: &mut impl FnMut(&mut K)) -> K {
+    let resource_version = obj.meta().resource_version.clone();
+    update(&mut obj);
+    obj.meta_mut().resource_version = resource_version;
+    obj
+}
+
+/// Exponential backoff between the attempts of a read-modify-write cycle
+pub(crate) struct ConflictBackoff {
+    retries: u32,
+    delay: Duration,
+}
+
+impl Default for ConflictBackoff {
+    fn default() -> Self {
+        Self {
+            retries: 5,
+            delay: Duration::from_millis(50),
+        }
+    }
+}
+
+impl ConflictBackoff {
+    /// Waits before the next attempt if `res` failed with a `409 Conflict`
+    ///
+    /// Returns `false` if `res` should be returned as-is, because it didn't conflict or we ran out of retries.
+    pub(crate) async fn retry<T>(&mut self, res: &Result<T>) -> bool {
+        match res {
+            Err(err) if err.is_conflict() && self.retries > 0 => {
+                tracing::debug!("conflict while updating, retrying in {:?}", self.delay);
+                tokio::time::sleep(self.delay).await;
+                self.retries -= 1;
+                self.delay *= 2;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
 impl<K> From<Api<K>> for Client {
     fn from(api: Api<K>) -> Self {
         api.client
     }
 }
+
+#[cfg(all(test, feature = "fake"))]
+mod tests {
+    use crate::{
+        api::{Api, ContinueExpiry, DeleteParams, ListParams, Patch, PatchParams, PostParams, ResourceExt},
+        fake::FakeApiServer,
+    };
+    use futures::TryStreamExt;
+    use k8s_openapi::api::core::v1::{ConfigMap, Pod};
+    use serde_json::json;
+
+    fn configmap(name: &str) -> ConfigMap {
+        serde_json::from_value(json!({ "metadata": { "name": name }, "data": { "key": "value" } })).unwrap()
+    }
+
+    #[tokio::test]
+    async fn get_opt_should_return_none_when_missing() {
+        let server = FakeApiServer::new();
+        let cms: Api<ConfigMap> = Api::namespaced(server.client(), "ns");
+        assert!(cms.get_opt("a").await.unwrap().is_none());
+        cms.create(&PostParams::default(), &configmap("a")).await.unwrap();
+        assert_eq!(cms.get_opt("a").await.unwrap().unwrap().data, configmap("a").data);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn update_with_should_retry_on_conflict() {
+        let server = FakeApiServer::new();
+        let cms: Api<ConfigMap> = Api::namespaced(server.client(), "ns");
+        cms.create(&PostParams::default(), &configmap("a")).await.unwrap();
+
+        let mut attempts = 0;
+        let updated = cms
+            .update_with("a", &PostParams::default(), |cm| {
+                attempts += 1;
+                if attempts == 1 {
+                    // Sneak in a conflicting write between the read and the replace
+                    tokio::task::block_in_place(|| {
+                        tokio::runtime::Handle::current().block_on(cms.patch(
+                            "a",
+                            &PatchParams::default(),
+                            &Patch::Merge(json!({ "data": { "other": "value" } })),
+                        ))
+                    })
+                    .unwrap();
+                }
+                cm.data
+                    .get_or_insert_with(Default::default)
+                    .insert("key".into(), "new".into());
+            })
+            .await
+            .unwrap();
+        assert_eq!(attempts, 2);
+        let data = updated.data.unwrap();
+        assert_eq!((data["key"].as_str(), data["other"].as_str()), ("new", "value"));
+
+        let pods: Api<Pod> = Api::namespaced(server.client(), "ns");
+        pods.create(
+            &PostParams::default(),
+            &serde_json::from_value(json!({ "metadata": { "name": "p" } })).unwrap(),
+        )
+        .await
+        .unwrap();
+        let pod = pods
+            .update_status_with("p", &PostParams::default(), |pod| {
+                pod.status.get_or_insert_with(Default::default).phase = Some("Running".into());
+            })
+            .await
+            .unwrap();
+        assert_eq!(pod.status.unwrap().phase.as_deref(), Some("Running"));
+    }
+
+    #[tokio::test]
+    async fn list_stream_should_follow_and_restart_continue_tokens() {
+        let server = FakeApiServer::new();
+        let cms: Api<ConfigMap> = Api::namespaced(server.client(), "ns");
+        for name in &["a", "b", "c"] {
+            cms.create(&PostParams::default(), &configmap(name))
+                .await
+                .unwrap();
+        }
+        let lp = ListParams::default().limit(2);
+        let names = cms
+            .list_stream(&lp)
+            .map_ok(|cm| cm.name())
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(names, vec!["a", "b", "c"]);
+        // Only the first page is requested at the given version
+        let cached = cms
+            .list_stream(&lp.clone().match_any())
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(cached.len(), 3);
+
+        // Continue tokens expire once the list they were issued for has been compacted away
+        let mut failing = Box::pin(cms.list_stream_with(&lp, ContinueExpiry::Fail));
+        assert_eq!(failing.try_next().await.unwrap().unwrap().name(), "a");
+        assert_eq!(failing.try_next().await.unwrap().unwrap().name(), "b");
+        cms.create(&PostParams::default(), &configmap("d")).await.unwrap();
+        server.compact();
+        assert!(failing.try_next().await.unwrap_err().is_gone());
+
+        let mut restarting = Box::pin(cms.list_stream(&lp));
+        assert_eq!(restarting.try_next().await.unwrap().unwrap().name(), "a");
+        assert_eq!(restarting.try_next().await.unwrap().unwrap().name(), "b");
+        cms.create(&PostParams::default(), &configmap("e")).await.unwrap();
+        server.compact();
+        let rest = restarting
+            .map_ok(|cm| cm.name())
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(rest, vec!["a", "b", "c", "d", "e"]);
+    }
+
+    #[tokio::test]
+    async fn dry_runs_should_not_persist_anything() {
+        let server = FakeApiServer::new();
+        let cms: Api<ConfigMap> = Api::namespaced(server.client(), "ns");
+        let apply = json!({
+            "apiVersion": "v1",
+            "kind": "ConfigMap",
+            "metadata": { "name": "a" },
+            "data": { "key": "preview" },
+        });
+        let preview = cms
+            .preview_apply("a", &PatchParams::apply("test"), &apply)
+            .await
+            .unwrap();
+        assert_eq!(preview.data.unwrap()["key"], "preview");
+        assert!(cms.get_opt("a").await.unwrap().is_none());
+
+        let dry_run = PostParams::default().dry_run();
+        cms.create(&dry_run, &configmap("a")).await.unwrap();
+        assert!(cms.get_opt("a").await.unwrap().is_none());
+
+        let created = cms.create(&PostParams::default(), &configmap("a")).await.unwrap();
+        let mut changed = created.clone();
+        changed.data = None;
+        cms.replace("a", &dry_run, &changed).await.unwrap();
+        cms.delete("a", &DeleteParams::default().dry_run()).await.unwrap();
+        cms.delete_collection(&DeleteParams::default().dry_run(), &ListParams::default())
+            .await
+            .unwrap();
+        assert_eq!(cms.get("a").await.unwrap(), created);
+    }
+}
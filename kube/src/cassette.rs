@@ -0,0 +1,461 @@
+//! Recording and replaying of HTTP traffic, for deterministic tests
+//!
+//! [`RecordLayer`] wraps a [`Service`](crate::Service) (usually one talking to a real cluster),
+//! and records every request and response that passes through it into a [`Cassette`].
+//! Streaming responses (such as watches) are recorded chunk by chunk as they are consumed.
+//! The cassette can then be [saved](Cassette::save) to a YAML file and, later on,
+//! [loaded](Cassette::load) and served back by [`Replay`] without a cluster.
+//!
+//! ```no_run
+//! use kube::{cassette::{Cassette, RecordLayer, Replay}, Client, Config, Service};
+//! use std::convert::TryFrom;
+//! use tower::Layer;
+//!
+//! # async fn wrapper() -> Result<(), Box<dyn std::error::Error>> {
+//! // Record against a real cluster..
+//! let cassette = Cassette::new();
+//! let service = Service::try_from(Config::infer().await?)?;
+//! let client = Client::new(Service::new(RecordLayer::new(cassette.clone()).layer(service)));
+//! client.apiserver_version().await?;
+//! cassette.save("tests/cassettes/version.yaml")?;
+//!
+//! // ..and replay it later
+//! let replay = Replay::new(Cassette::load("tests/cassettes/version.yaml")?);
+//! let client = Client::new(Service::new(replay));
+//! client.apiserver_version().await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Requests are matched on their method, path and query parameters (in any order). Each recorded interaction
+//! is only replayed once, in the order that they were recorded, so repeated requests (such as a `watcher`
+//! re-establishing its watch) will receive successive responses. Request headers are not recorded, to avoid
+//! storing credentials in the cassette. Bodies are assumed to be UTF-8, and upgraded connections (such as
+//! `exec` and `attach`) are not supported.
+
+use bytes::Bytes;
+use futures::{future::BoxFuture, stream, Stream};
+use http::{Request, Response};
+use hyper::Body;
+use pin_project::{pin_project, pinned_drop};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    convert::Infallible,
+    fs::File,
+    path::Path,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+use thiserror::Error;
+use tower::{BoxError, Layer, Service};
+
+/// Possible errors when loading or saving a [`Cassette`]
+#[derive(Error, Debug)]
+pub enum CassetteError {
+    /// The cassette file could not be opened or created
+    #[error("Unable to access cassette file: {0}")]
+    Io(#[source] std::io::Error),
+    /// The cassette could not be serialized or deserialized
+    #[error("Unable to parse cassette: {0}")]
+    Yaml(#[source] serde_yaml::Error),
+}
+
+/// A recorded request
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RecordedRequest {
+    /// The HTTP method, such as `GET`
+    pub method: String,
+    /// The path of the url, such as `/api/v1/namespaces/default/pods`
+    pub path: String,
+    /// The query string of the url, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    /// The request body
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub body: String,
+}
+
+impl RecordedRequest {
+    fn query_pairs(&self) -> Vec<(String, String)> {
+        let mut pairs = url::form_urlencoded::parse(self.query.as_deref().unwrap_or_default().as_bytes())
+            .into_owned()
+            .collect::<Vec<_>>();
+        pairs.sort();
+        pairs
+    }
+
+    /// Whether `other` is the same request, disregarding the body and the order of the query parameters
+    fn matches(&self, other: &RecordedRequest) -> bool {
+        self.method == other.method && self.path == other.path && self.query_pairs() == other.query_pairs()
+    }
+}
+
+/// A recorded response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RecordedResponse {
+    /// The HTTP status code
+    pub status: u16,
+    /// The response headers
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// The response body, split into the chunks that it was received in
+    #[serde(default)]
+    pub body: Vec<String>,
+}
+
+/// A request along with the response that it received
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Interaction {
+    /// The request that was sent
+    pub request: RecordedRequest,
+    /// The response that was received
+    pub response: RecordedResponse,
+}
+
+/// A list of recorded [`Interaction`]s
+///
+/// Cloning will produce a new reference to the same cassette.
+#[derive(Clone, Debug, Default)]
+pub struct Cassette {
+    // Slots are reserved when a request is sent, so that interactions are kept in request order
+    // even if their (streaming) responses finish in a different order.
+    interactions: Arc<Mutex<Vec<Option<Interaction>>>>,
+}
+
+impl Cassette {
+    /// Creates an empty cassette
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a cassette from a list of interactions
+    pub fn from_interactions(interactions: Vec<Interaction>) -> Self {
+        Cassette {
+            interactions: Arc::new(Mutex::new(interactions.into_iter().map(Some).collect())),
+        }
+    }
+
+    /// Loads a cassette from a YAML file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CassetteError> {
+        let file = File::open(path).map_err(CassetteError::Io)?;
+        let interactions = serde_yaml::from_reader(file).map_err(CassetteError::Yaml)?;
+        Ok(Self::from_interactions(interactions))
+    }
+
+    /// Saves the completed interactions to a YAML file
+    ///
+    /// Responses that are still being streamed are not included.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CassetteError> {
+        let file = File::create(path).map_err(CassetteError::Io)?;
+        serde_yaml::to_writer(file, &self.interactions()).map_err(CassetteError::Yaml)
+    }
+
+    /// Returns the completed interactions, in the order that their requests were sent
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.interactions
+            .lock()
+            .unwrap()
+            .iter()
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    fn reserve(&self) -> usize {
+        let mut interactions = self.interactions.lock().unwrap();
+        interactions.push(None);
+        interactions.len() - 1
+    }
+
+    fn complete(&self, slot: usize, interaction: Interaction) {
+        self.interactions.lock().unwrap()[slot] = Some(interaction);
+    }
+}
+
+/// A [`Layer`] that records all traffic into a [`Cassette`]
+#[derive(Clone, Debug)]
+pub struct RecordLayer {
+    cassette: Cassette,
+}
+
+impl RecordLayer {
+    /// Creates a layer that records into `cassette`
+    pub fn new(cassette: Cassette) -> Self {
+        Self { cassette }
+    }
+}
+
+impl<S> Layer<S> for RecordLayer {
+    type Service = Record<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        Record {
+            service,
+            cassette: self.cassette.clone(),
+        }
+    }
+}
+
+/// A [`Service`] that records all traffic to the wrapped service into a [`Cassette`]
+///
+/// Created by [`RecordLayer`].
+#[derive(Clone, Debug)]
+pub struct Record<S> {
+    service: S,
+    cassette: Cassette,
+}
+
+impl<S> Service<Request<Body>> for Record<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Error: Into<BoxError>,
+    S::Future: Send,
+{
+    type Error = BoxError;
+    type Future = BoxFuture<'static, Result<Response<Body>, BoxError>>;
+    type Response = Response<Body>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        // Take the service that was polled ready, and leave a fresh clone in its place
+        let clone = self.service.clone();
+        let mut service = std::mem::replace(&mut self.service, clone);
+        let cassette = self.cassette.clone();
+        let slot = cassette.reserve();
+        Box::pin(async move {
+            let (parts, body) = req.into_parts();
+            let body = hyper::body::to_bytes(body).await?;
+            let request = RecordedRequest {
+                method: parts.method.to_string(),
+                path: parts.uri.path().to_string(),
+                query: parts.uri.query().map(String::from),
+                body: String::from_utf8_lossy(&body).into_owned(),
+            };
+            let res = service
+                .call(Request::from_parts(parts, Body::from(body)))
+                .await
+                .map_err(Into::into)?;
+            let (parts, body) = res.into_parts();
+            let response = RecordedResponse {
+                status: parts.status.as_u16(),
+                headers: parts
+                    .headers
+                    .iter()
+                    .map(|(name, value)| {
+                        (
+                            name.to_string(),
+                            String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                        )
+                    })
+                    .collect(),
+                body: Vec::new(),
+            };
+            let recording = RecordingBody {
+                body,
+                cassette,
+                slot,
+                interaction: Some(Interaction { request, response }),
+            };
+            Ok(Response::from_parts(parts, Body::wrap_stream(recording)))
+        })
+    }
+}
+
+/// Passes through a response body, and commits the interaction to the cassette once it is dropped
+#[pin_project(PinnedDrop)]
+struct RecordingBody {
+    #[pin]
+    body: Body,
+    cassette: Cassette,
+    slot: usize,
+    interaction: Option<Interaction>,
+}
+
+impl Stream for RecordingBody {
+    type Item = Result<Bytes, hyper::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let chunk = this.body.poll_next(cx);
+        if let (Poll::Ready(Some(Ok(chunk))), Some(interaction)) = (&chunk, this.interaction) {
+            interaction
+                .response
+                .body
+                .push(String::from_utf8_lossy(chunk).into_owned());
+        }
+        chunk
+    }
+}
+
+#[pinned_drop]
+impl PinnedDrop for RecordingBody {
+    fn drop(self: Pin<&mut Self>) {
+        let this = self.project();
+        if let Some(interaction) = this.interaction.take() {
+            this.cassette.complete(*this.slot, interaction);
+        }
+    }
+}
+
+/// A [`Service`] that answers requests with the responses recorded in a [`Cassette`]
+///
+/// Requests that don't match any remaining interaction fail.
+///
+/// Cloning will produce a new reference to the same replay, sharing the same remaining interactions.
+#[derive(Clone, Debug)]
+pub struct Replay {
+    interactions: Vec<Interaction>,
+    used: Arc<Mutex<Vec<bool>>>,
+}
+
+impl Replay {
+    /// Creates a service that replays `cassette`
+    pub fn new(cassette: Cassette) -> Self {
+        let interactions = cassette.interactions();
+        Replay {
+            used: Arc::new(Mutex::new(vec![false; interactions.len()])),
+            interactions,
+        }
+    }
+
+    /// Returns the interactions that have not been replayed yet
+    pub fn remaining(&self) -> Vec<Interaction> {
+        let used = self.used.lock().unwrap();
+        self.interactions
+            .iter()
+            .zip(used.iter())
+            .filter(|(_, used)| !**used)
+            .map(|(interaction, _)| interaction.clone())
+            .collect()
+    }
+
+    fn take(&self, request: &RecordedRequest) -> Option<&Interaction> {
+        let mut used = self.used.lock().unwrap();
+        let index = self
+            .interactions
+            .iter()
+            .zip(used.iter())
+            .position(|(interaction, used)| !used && interaction.request.matches(request))?;
+        used[index] = true;
+        Some(&self.interactions[index])
+    }
+}
+
+impl Service<Request<Body>> for Replay {
+    type Error = BoxError;
+    type Future = BoxFuture<'static, Result<Response<Body>, BoxError>>;
+    type Response = Response<Body>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let request = RecordedRequest {
+            method: req.method().to_string(),
+            path: req.uri().path().to_string(),
+            query: req.uri().query().map(String::from),
+            body: String::new(),
+        };
+        let response = self
+            .take(&request)
+            .map(|interaction| interaction.response.clone());
+        Box::pin(async move {
+            let recorded = response.ok_or_else(|| {
+                format!(
+                    "no recorded interaction left for {} {}?{}",
+                    request.method,
+                    request.path,
+                    request.query.unwrap_or_default()
+                )
+            })?;
+            let mut res = Response::builder().status(recorded.status);
+            for (name, value) in &recorded.headers {
+                res = res.header(name.as_str(), value.as_str());
+            }
+            let chunks = recorded
+                .body
+                .into_iter()
+                .map(|chunk| Ok::<_, Infallible>(Bytes::from(chunk)));
+            Ok(res.body(Body::wrap_stream(stream::iter(chunks)))?)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cassette, RecordLayer, Replay};
+    use crate::{api::Api, Client, Service};
+    use futures::{stream, StreamExt};
+    use http::{Request, Response};
+    use hyper::Body;
+    use k8s_openapi::api::core::v1::ConfigMap;
+    use std::convert::Infallible;
+    use tower::{BoxError, Layer};
+
+    const CONFIGMAP: &str =
+        r#"{"apiVersion":"v1","kind":"ConfigMap","metadata":{"name":"a","resourceVersion":"1"}}"#;
+
+    /// Serves a single `ConfigMap` for gets, and one event per chunk for watches
+    fn upstream() -> Service {
+        Service::new(tower::service_fn(|req: Request<Body>| async move {
+            let body = if req.uri().query().unwrap_or_default().contains("watch=true") {
+                let events = ["ADDED", "MODIFIED"]
+                    .iter()
+                    .map(|kind| {
+                        Ok::<_, Infallible>(format!("{{\"type\":\"{}\",\"object\":{}}}\n", kind, CONFIGMAP))
+                    })
+                    .collect::<Vec<_>>();
+                Body::wrap_stream(stream::iter(events))
+            } else {
+                Body::from(CONFIGMAP)
+            };
+            Ok::<_, BoxError>(Response::new(body))
+        }))
+    }
+
+    #[tokio::test]
+    async fn replay_should_serve_recorded_interactions() {
+        let cassette = Cassette::new();
+        let client = Client::new(Service::new(RecordLayer::new(cassette.clone()).layer(upstream())));
+        let cms: Api<ConfigMap> = Api::namespaced(client, "ns");
+        cms.get("a").await.unwrap();
+        let events = cms
+            .watch(&Default::default(), "0")
+            .await
+            .unwrap()
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(events.len(), 2);
+
+        let interactions = cassette.interactions();
+        assert_eq!(interactions.len(), 2);
+        assert_eq!(interactions[0].request.path, "/api/v1/namespaces/ns/configmaps/a");
+        assert_eq!(interactions[1].response.body.len(), 2);
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        cassette.save(file.path()).unwrap();
+        let replay = Replay::new(Cassette::load(file.path()).unwrap());
+        assert_eq!(replay.remaining(), interactions);
+        let cms: Api<ConfigMap> = Api::namespaced(Client::new(Service::new(replay.clone())), "ns");
+        let events = cms
+            .watch(&Default::default(), "0")
+            .await
+            .unwrap()
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            cms.get("a").await.unwrap().metadata.resource_version.as_deref(),
+            Some("1")
+        );
+        assert!(replay.remaining().is_empty());
+        // Every interaction is only replayed once
+        assert!(cms.get("a").await.is_err());
+    }
+}
@@ -184,6 +184,27 @@ impl Discovery {
         Ok(Discovery { groups })
     }
 
+    /// Builds discovery information from resource lists, without querying a cluster
+    #[cfg(all(test, feature = "fake"))]
+    pub(crate) fn from_resource_lists(lists: Vec<APIResourceList>) -> Self {
+        let mut groups = HashMap::new();
+        for list in lists {
+            let (group, version) = Self::parse_api_version(&list.group_version).unwrap();
+            let (group, version) = (group.to_string(), version.to_string());
+            groups
+                .entry(group.clone())
+                .or_insert_with(|| Group {
+                    name: group,
+                    versions_and_resources: Vec::new(),
+                    preferred_version: None,
+                })
+                .versions_and_resources
+                .push(GroupVersionData::new(version, list));
+        }
+        groups.values_mut().for_each(|group| group.sort_versions());
+        Discovery { groups }
+    }
+
     /// Utility function that splits apiVersion into a group and version
     /// that can be later used with this type.
     pub fn parse_api_version(api_version: &str) -> Option<(&str, &str)> {
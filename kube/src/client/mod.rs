@@ -18,7 +18,7 @@ use futures::{self, Stream, StreamExt, TryStream, TryStreamExt};
 use http::{self, Request, Response, StatusCode};
 use hyper::Body;
 use k8s_openapi::apimachinery::pkg::apis::meta::v1 as k8s_meta_v1;
-use serde::{de::DeserializeOwned, Deserialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::{self, Value};
 use tokio_util::{
     codec::{FramedRead, LinesCodec, LinesCodecError},
@@ -333,6 +333,7 @@ fn handle_api_errors(text: &str, s: StatusCode) -> Result<()> {
                 code: s.as_u16(),
                 message: format!("{:?}", text),
                 reason: "Failed to parse error data".into(),
+                details: None,
             };
             tracing::debug!("Unsuccessful: {:?} (reconstruct)", ae);
             Err(Error::Api(ae))
@@ -372,7 +373,7 @@ pub struct Status {
 }
 
 /// Status details object on the [`Status`] object
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, Eq, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[allow(missing_docs)]
 pub struct StatusDetails {
@@ -386,12 +387,16 @@ pub struct StatusDetails {
     pub uid: String,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub causes: Vec<StatusCause>,
-    #[serde(default, skip_serializing_if = "num::Zero::is_zero")]
+    #[serde(default, skip_serializing_if = "is_zero")]
     pub retry_after_seconds: u32,
 }
 
+fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
 /// Status cause object on the [`StatusDetails`] object
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, Eq, PartialEq)]
 #[allow(missing_docs)]
 pub struct StatusCause {
     #[serde(default, skip_serializing_if = "String::is_empty")]
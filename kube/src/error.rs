@@ -1,5 +1,6 @@
 //! Error handling in [`kube`][crate]
 
+use crate::client::{StatusCause, StatusDetails};
 use http::header::InvalidHeaderValue;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
@@ -70,6 +71,19 @@ pub enum Error {
     #[error("Request validation failed with {0}")]
     RequestValidation(String),
 
+    /// Objects were still not deleted when waiting for their deletion timed out
+    ///
+    /// Returned by [`Api::delete_and_wait`](crate::Api::delete_and_wait) and
+    /// [`Api::delete_collection_and_wait`](crate::Api::delete_collection_and_wait).
+    #[error("Timed out waiting for deletion of {}", display_pending(.0))]
+    DeletionTimeout(Vec<PendingDeletion>),
+
+    /// A manifest could not be parsed into objects
+    ///
+    /// Returned by [`Manifest::from_yaml`](crate::api::Manifest::from_yaml).
+    #[error("Error parsing manifest: {0}")]
+    ManifestParse(#[source] serde_yaml::Error),
+
     /// Configuration error
     #[error("Error loading kubeconfig: {0}")]
     Kubeconfig(#[from] ConfigError),
@@ -258,9 +272,30 @@ impl From<OAuthError> for Error {
     }
 }
 
+/// An object that was still not deleted when waiting for its deletion timed out
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PendingDeletion {
+    /// The name of the object
+    pub name: String,
+    /// The finalizers that were still blocking the deletion
+    ///
+    /// If this is empty then the deletion is waiting for something else, such as the graceful termination
+    /// of a `Pod`.
+    pub finalizers: Vec<String>,
+}
+
+fn display_pending(pending: &[PendingDeletion]) -> String {
+    pending
+        .iter()
+        .map(|p| format!("{} (finalizers: {:?})", p.name, p.finalizers))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 /// An error response from the API.
 #[derive(Error, Deserialize, Serialize, Debug, Clone, Eq, PartialEq)]
 #[error("{message}: {reason}")]
+#[non_exhaustive]
 pub struct ErrorResponse {
     /// The status
     pub status: String,
@@ -272,4 +307,152 @@ pub struct ErrorResponse {
     pub reason: String,
     /// The error code
     pub code: u16,
+    /// Extended data about the error, such as the fields that failed validation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<Box<StatusDetails>>,
+}
+
+impl ErrorResponse {
+    /// The individual causes of the error, if the API server reported any
+    pub fn causes(&self) -> &[StatusCause] {
+        self.details
+            .as_ref()
+            .map_or(&[], |details| details.causes.as_slice())
+    }
+}
+
+impl Error {
+    /// The `Status` returned by the API server, if it rejected the request
+    pub fn api_status(&self) -> Option<&ErrorResponse> {
+        match self {
+            Error::Api(status) => Some(status),
+            _ => None,
+        }
+    }
+
+    fn has_api_code(&self, code: u16) -> bool {
+        self.api_status().map_or(false, |status| status.code == code)
+    }
+
+    /// Whether the object (or its namespace) does not exist
+    pub fn is_not_found(&self) -> bool {
+        self.has_api_code(404)
+    }
+
+    /// Whether the request conflicted with a concurrent modification of the object
+    ///
+    /// This is usually caused by an outdated `resourceVersion`, and can be resolved by reading the object again
+    /// and retrying.
+    pub fn is_conflict(&self) -> bool {
+        self.has_api_code(409) && !self.is_already_exists()
+    }
+
+    /// Whether an object could not be created because one with the same name already exists
+    pub fn is_already_exists(&self) -> bool {
+        self.api_status().map_or(false, |status| {
+            status.code == 409 && status.reason == "AlreadyExists"
+        })
+    }
+
+    /// Whether a `resourceVersion` or continue token is too old to be served (`410 Gone`)
+    ///
+    /// The request can be retried from a fresh list.
+    pub fn is_gone(&self) -> bool {
+        self.has_api_code(410)
+    }
+
+    /// Whether the client is not allowed to perform the request
+    pub fn is_forbidden(&self) -> bool {
+        self.has_api_code(403)
+    }
+
+    /// Whether the error is likely to be temporary, so that the request may succeed if retried
+    ///
+    /// This covers throttling (`429 Too Many Requests`), server errors and timeouts, and dropped connections.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::Api(status) => matches!(status.code, 429 | 500 | 502 | 503 | 504),
+            Error::Connection(err) | Error::ReadEvents(err) => is_retryable_io_error(err),
+            Error::HyperError(err) => is_retryable_hyper_error(err),
+            Error::Service(err) => {
+                err.downcast_ref::<hyper::Error>()
+                    .map_or(false, is_retryable_hyper_error)
+                    || err
+                        .downcast_ref::<std::io::Error>()
+                        .map_or(false, is_retryable_io_error)
+            }
+            _ => false,
+        }
+    }
+}
+
+fn is_retryable_hyper_error(err: &hyper::Error) -> bool {
+    err.is_connect()
+        || err.is_closed()
+        || err.is_incomplete_message()
+        || std::error::Error::source(err)
+            .and_then(|source| source.downcast_ref::<std::io::Error>())
+            .map_or(false, is_retryable_io_error)
+}
+
+fn is_retryable_io_error(err: &std::io::Error) -> bool {
+    use std::io::ErrorKind;
+    matches!(
+        err.kind(),
+        ErrorKind::ConnectionRefused
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::BrokenPipe
+            | ErrorKind::TimedOut
+            | ErrorKind::UnexpectedEof
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, ErrorResponse};
+
+    fn api_error(code: u16, reason: &str) -> Error {
+        Error::Api(ErrorResponse {
+            status: "Failure".to_string(),
+            message: String::new(),
+            reason: reason.to_string(),
+            code,
+            details: None,
+        })
+    }
+
+    #[test]
+    fn api_errors_should_be_classified_by_code_and_reason() {
+        assert!(api_error(404, "NotFound").is_not_found());
+        assert!(api_error(409, "Conflict").is_conflict());
+        assert!(!api_error(409, "Conflict").is_already_exists());
+        assert!(api_error(409, "AlreadyExists").is_already_exists());
+        assert!(!api_error(409, "AlreadyExists").is_conflict());
+        assert!(api_error(403, "Forbidden").is_forbidden());
+        assert!(api_error(410, "Expired").is_gone());
+        assert!(api_error(429, "TooManyRequests").is_retryable());
+        assert!(api_error(503, "ServiceUnavailable").is_retryable());
+        assert!(!api_error(400, "BadRequest").is_retryable());
+        assert!(!Error::RequestBuild.is_not_found());
+    }
+
+    #[test]
+    fn io_errors_should_be_retryable_if_transient() {
+        let io_error = |kind| Error::Connection(std::io::Error::new(kind, "test"));
+        assert!(io_error(std::io::ErrorKind::ConnectionReset).is_retryable());
+        assert!(io_error(std::io::ErrorKind::TimedOut).is_retryable());
+        assert!(!io_error(std::io::ErrorKind::PermissionDenied).is_retryable());
+    }
+
+    #[test]
+    fn status_details_should_be_parsed() {
+        let status = serde_json::from_str::<ErrorResponse>(
+            r#"{"kind":"Status","apiVersion":"v1","metadata":{},"status":"Failure","message":"Pod \"x\" is invalid","reason":"Invalid","details":{"name":"x","kind":"pods","causes":[{"reason":"FieldValueRequired","message":"Required value","field":"spec.containers"}]},"code":422}"#,
+        )
+        .unwrap();
+        assert_eq!(status.causes().len(), 1);
+        assert_eq!(status.causes()[0].field, "spec.containers");
+        assert_eq!(status.details.unwrap().name, "x");
+    }
 }
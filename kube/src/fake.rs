@@ -0,0 +1,1168 @@
+//! An in-memory stand-in for the Kubernetes API server, for use in tests
+//!
+//! [`FakeApiServer`] implements [`tower::Service`], so it can be plugged into a [`Client`]
+//! with [`Client::new`], and everything built on top of it ([`Api`](crate::Api), and the watchers,
+//! reflectors and controllers of `kube-runtime`) can be exercised without a real cluster.
+//!
+//! ```
+//! use k8s_openapi::api::core::v1::ConfigMap;
+//! use kube::{api::{Api, PostParams}, fake::FakeApiServer};
+//!
+//! # async fn wrapper() -> Result<(), kube::Error> {
+//! let server = FakeApiServer::new();
+//! let cms: Api<ConfigMap> = Api::namespaced(server.client(), "default");
+//! let mut cm = ConfigMap::default();
+//! cm.metadata.name = Some("my-config".to_string());
+//! cms.create(&PostParams::default(), &cm).await?;
+//! assert!(cms.get("my-config").await?.metadata.uid.is_some());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! All objects are stored as JSON, so both typed resources and [`DynamicObject`](crate::api::DynamicObject)s
+//! work, and no resource types need to be registered up front. The server emulates a subset of the real
+//! API server's behaviour:
+//!
+//! - `get`, `list` (with label and field selectors, `limit` and `continue`), `watch`, `create`
+//!   (including `generateName`), `replace`, `patch`, `delete` and `delete_collection`,
+//!   as well as the `status` subresource
+//! - a single `resourceVersion` sequence shared by all objects, with optimistic concurrency on
+//!   `replace` and merge patches
+//! - `metadata.generation`, which is bumped whenever anything outside of `metadata` and `status` changes
+//! - writes to the main resource leave the `status` alone, and writes to `status` leave everything else alone
+//! - deletion is deferred by setting `metadata.deletionTimestamp` until all `metadata.finalizers` are removed
+//! - watches replay the changes since the requested `resourceVersion`, and fail with `410 Gone` if it
+//!   has been [compacted](FakeApiServer::compact) away (as do continue tokens of paginated lists)
+//! - watches without a `resourceVersion` start with the existing objects, followed by the
+//!   `k8s.io/initial-events-end` bookmark if `sendInitialEvents` is requested
+//!
+//! Server-side apply is approximated by a merge patch that creates the object if it doesn't exist
+//! ("apply-lite"), without any field ownership tracking. Strategic merge patches are also treated as
+//! plain merge patches, and JSON patches require the `jsonpatch` feature. Discovery and other
+//! subresources are not supported.
+
+use crate::{Client, Resource, Service};
+use bytes::Bytes;
+use chrono::{SecondsFormat, Utc};
+use futures::{channel::mpsc, future::BoxFuture, StreamExt};
+use http::{Method, Request, Response, StatusCode};
+use hyper::Body;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Map, Value};
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::Infallible,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Duration,
+};
+use tower::BoxError;
+
+/// An in-memory stand-in for the Kubernetes API server
+///
+/// See the [module documentation](self) for which parts of the API are supported.
+///
+/// Cloning will produce a new reference to the same server.
+#[derive(Clone, Default)]
+pub struct FakeApiServer {
+    state: Arc<Mutex<State>>,
+}
+
+impl FakeApiServer {
+    /// Creates an empty server
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a [`Client`] that talks to this server
+    pub fn client(&self) -> Client {
+        Client::new(Service::new(self.clone()))
+    }
+
+    /// Stores an object directly, as if it had been created through the API
+    ///
+    /// Unlike a regular `create`, which drops the `status` of the object, this stores the `status` as given.
+    /// Returns the object as stored, with `uid`, `resourceVersion` and friends populated.
+    pub fn insert<K>(&self, obj: &K) -> K
+    where
+        K: Resource + Serialize + DeserializeOwned,
+        K::DynamicType: Default,
+    {
+        self.insert_with(obj, &Default::default())
+    }
+
+    /// Stores an object directly, using an explicit [`Resource::DynamicType`]
+    ///
+    /// See [`FakeApiServer::insert`] for details.
+    pub fn insert_with<K>(&self, obj: &K, dyntype: &K::DynamicType) -> K
+    where
+        K: Resource + Serialize + DeserializeOwned,
+    {
+        let namespace = obj.meta().namespace.as_deref();
+        let target = parse_path(&K::url_path(dyntype, namespace)).expect("resource url should be valid");
+        let value = serde_json::to_value(obj).expect("object should be serializable");
+        let mut state = self.state.lock().unwrap();
+        let value = state
+            .create(&target, value, true, false)
+            .expect("object should be insertable");
+        serde_json::from_value(value).expect("stored object should be deserializable")
+    }
+
+    /// Forgets the history of all changes made so far
+    ///
+    /// Watches started from an older `resourceVersion` will fail with `410 Gone`,
+    /// which forces watchers to relist.
+    pub fn compact(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.compacted = state.resource_version;
+        for collection in state.collections.values_mut() {
+            collection.history.clear();
+        }
+    }
+
+    /// Ends all currently open watches, as if the connections had been dropped
+    pub fn disconnect_watches(&self) {
+        let mut state = self.state.lock().unwrap();
+        for collection in state.collections.values_mut() {
+            collection.watches.clear();
+        }
+    }
+}
+
+impl tower::Service<Request<Body>> for FakeApiServer {
+    type Error = BoxError;
+    type Future = BoxFuture<'static, Result<Response<Body>, BoxError>>;
+    type Response = Response<Body>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let state = self.state.clone();
+        Box::pin(async move {
+            let (parts, body) = req.into_parts();
+            let body = hyper::body::to_bytes(body).await?;
+            let mut state = state.lock().unwrap();
+            Ok(state
+                .handle(&parts, &body)
+                .unwrap_or_else(|err| err.into_response()))
+        })
+    }
+}
+
+/// A failed request, rendered as a `Status` object
+#[derive(Debug)]
+struct Failure {
+    code: StatusCode,
+    reason: &'static str,
+    message: String,
+}
+
+impl Failure {
+    fn new(code: StatusCode, reason: &'static str, message: impl Into<String>) -> Self {
+        Failure {
+            code,
+            reason,
+            message: message.into(),
+        }
+    }
+
+    fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "BadRequest", message)
+    }
+
+    fn not_found(target: &Target, name: &str) -> Self {
+        Self::new(
+            StatusCode::NOT_FOUND,
+            "NotFound",
+            format!("{} \"{}\" not found", target.plural, name),
+        )
+    }
+
+    fn conflict(target: &Target, name: &str) -> Self {
+        Self::new(
+            StatusCode::CONFLICT,
+            "Conflict",
+            format!(
+                "Operation cannot be fulfilled on {} \"{}\": the object has been modified; please apply your changes to the latest version and try again",
+                target.plural, name
+            ),
+        )
+    }
+
+    fn status(&self) -> Value {
+        json!({
+            "kind": "Status",
+            "apiVersion": "v1",
+            "metadata": {},
+            "status": "Failure",
+            "message": self.message,
+            "reason": self.reason,
+            "code": self.code.as_u16(),
+        })
+    }
+
+    fn into_response(self) -> Response<Body> {
+        json_response(self.code, &self.status())
+    }
+}
+
+type Result<T, E = Failure> = std::result::Result<T, E>;
+
+fn json_response(code: StatusCode, value: &Value) -> Response<Body> {
+    Response::builder()
+        .status(code)
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .body(Body::from(value.to_string()))
+        .expect("response should be valid")
+}
+
+/// The resource (and optionally object) that a request refers to
+struct Target {
+    /// The url path of the collection, without the namespace (such as `apis/apps/v1/deployments`)
+    collection: String,
+    api_version: String,
+    plural: String,
+    namespace: Option<String>,
+    name: Option<String>,
+    subresource: Option<String>,
+}
+
+fn parse_path(path: &str) -> Option<Target> {
+    let segments = path.trim_matches('/').split('/').collect::<Vec<_>>();
+    let prefix_len = match segments.first() {
+        Some(&"api") => 2,
+        Some(&"apis") => 3,
+        _ => return None,
+    };
+    if segments.len() <= prefix_len {
+        return None;
+    }
+    let (prefix, mut rest) = segments.split_at(prefix_len);
+    let mut namespace = None;
+    // `namespaces/{name}/{subresource}` refers to the namespace itself
+    if rest.len() >= 3 && rest[0] == "namespaces" && !(rest.len() == 3 && rest[2] == "status") {
+        namespace = Some(rest[1].to_string());
+        rest = &rest[2..];
+    }
+    if rest.len() > 3 || rest.iter().any(|segment| segment.is_empty()) {
+        return None;
+    }
+    Some(Target {
+        collection: format!("{}/{}", prefix.join("/"), rest[0]),
+        api_version: prefix[1..].join("/"),
+        plural: rest[0].to_string(),
+        namespace,
+        name: rest.get(1).map(|name| name.to_string()),
+        subresource: rest.get(2).map(|sub| sub.to_string()),
+    })
+}
+
+/// A single requirement of a label or field selector
+enum Requirement {
+    Equals(String, String),
+    NotEquals(String, String),
+    In(String, Vec<String>),
+    NotIn(String, Vec<String>),
+    Exists(String),
+    DoesNotExist(String),
+}
+
+impl Requirement {
+    fn matches(&self, lookup: impl Fn(&str) -> Option<String>) -> bool {
+        match self {
+            Requirement::Equals(key, value) => lookup(key).as_ref() == Some(value),
+            Requirement::NotEquals(key, value) => lookup(key).as_ref() != Some(value),
+            Requirement::In(key, values) => lookup(key).map_or(false, |v| values.contains(&v)),
+            Requirement::NotIn(key, values) => lookup(key).map_or(true, |v| !values.contains(&v)),
+            Requirement::Exists(key) => lookup(key).is_some(),
+            Requirement::DoesNotExist(key) => lookup(key).is_none(),
+        }
+    }
+}
+
+fn parse_selector(selector: &str) -> Result<Vec<Requirement>> {
+    let invalid = || Failure::bad_request(format!("unable to parse requirement: {:?}", selector));
+    // Split on the commas that are not inside of a set
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in selector.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&selector[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&selector[start..]);
+
+    let mut requirements = Vec::new();
+    for part in parts.into_iter().map(str::trim).filter(|part| !part.is_empty()) {
+        let set = |op: &str| {
+            let (key, values) = part.split_at(part.find(op)?);
+            let values = values[op.len()..].trim().strip_prefix('(')?.strip_suffix(')')?;
+            Some((
+                key.trim().to_string(),
+                values
+                    .split(',')
+                    .map(|v| v.trim().to_string())
+                    .collect::<Vec<_>>(),
+            ))
+        };
+        let requirement = if let Some((key, value)) = part.split_once("!=") {
+            Requirement::NotEquals(key.trim().to_string(), value.trim().to_string())
+        } else if let Some((key, value)) = part.split_once("==").or_else(|| part.split_once('=')) {
+            Requirement::Equals(key.trim().to_string(), value.trim().to_string())
+        } else if let Some((key, values)) = set(" notin ") {
+            Requirement::NotIn(key, values)
+        } else if let Some((key, values)) = set(" in ") {
+            Requirement::In(key, values)
+        } else if let Some(key) = part.strip_prefix('!') {
+            Requirement::DoesNotExist(key.trim().to_string())
+        } else if !part.contains(|c: char| c.is_whitespace() || c == '(' || c == ')') {
+            Requirement::Exists(part.to_string())
+        } else {
+            return Err(invalid());
+        };
+        requirements.push(requirement);
+    }
+    Ok(requirements)
+}
+
+/// Looks up a dotted field path (such as `status.phase`) in an object
+fn field(obj: &Value, path: &str) -> Option<String> {
+    match path.split('.').try_fold(obj, |value, key| value.get(key))? {
+        Value::String(s) => Some(s.clone()),
+        Value::Null => None,
+        other => Some(other.to_string()),
+    }
+}
+
+/// Decides which objects a list, watch or delete collection applies to
+struct Filter {
+    namespace: Option<String>,
+    labels: Vec<Requirement>,
+    fields: Vec<Requirement>,
+}
+
+impl Filter {
+    fn from_query(target: &Target, query: &HashMap<String, String>) -> Result<Self> {
+        let selector = |key: &str| {
+            query
+                .get(key)
+                .map_or_else(|| Ok(Vec::new()), |s| parse_selector(s))
+        };
+        Ok(Filter {
+            namespace: target.namespace.clone(),
+            labels: selector("labelSelector")?,
+            fields: selector("fieldSelector")?,
+        })
+    }
+
+    fn matches(&self, obj: &Value) -> bool {
+        let meta = &obj["metadata"];
+        self.namespace
+            .as_deref()
+            .map_or(true, |ns| meta["namespace"].as_str() == Some(ns))
+            && self
+                .labels
+                .iter()
+                .all(|req| req.matches(|key| meta["labels"][key].as_str().map(String::from)))
+            && self.fields.iter().all(|req| req.matches(|key| field(obj, key)))
+    }
+
+    /// Translates a change into the watch event (if any) that it causes for this filter
+    ///
+    /// Objects that start or stop matching the filter are reported as added or deleted respectively.
+    fn event(&self, change: &Change) -> Option<Value> {
+        let was_visible = change.old.as_ref().map_or(false, |old| self.matches(old));
+        let is_visible = self.matches(&change.new);
+        let kind = match (was_visible, is_visible, change.deleted) {
+            (true, _, true) => "DELETED",
+            (_, _, true) | (false, false, _) => return None,
+            (false, true, false) => "ADDED",
+            (true, true, false) => "MODIFIED",
+            (true, false, false) => "DELETED",
+        };
+        Some(json!({ "type": kind, "object": change.new }))
+    }
+}
+
+/// A single write to an object
+struct Change {
+    resource_version: u64,
+    old: Option<Value>,
+    new: Value,
+    deleted: bool,
+}
+
+struct Watch {
+    filter: Filter,
+    sender: mpsc::UnboundedSender<Result<Bytes, Infallible>>,
+}
+
+impl Watch {
+    /// Sends an event to the watcher, returning `false` if the watch has been closed
+    fn send(&self, event: &Value) -> bool {
+        let mut line = event.to_string();
+        line.push('\n');
+        self.sender.unbounded_send(Ok(Bytes::from(line))).is_ok()
+    }
+}
+
+/// All objects of a single resource type
+#[derive(Default)]
+struct Collection {
+    objects: BTreeMap<(Option<String>, String), Value>,
+    history: Vec<Change>,
+    watches: Vec<Watch>,
+}
+
+#[derive(Default)]
+struct State {
+    resource_version: u64,
+    compacted: u64,
+    collections: HashMap<String, Collection>,
+}
+
+/// The parts of an object that are managed by the server, and preserved across updates
+const SERVER_FIELDS: &[&str] = &["uid", "creationTimestamp", "deletionTimestamp", "generation"];
+
+fn metadata_mut(obj: &mut Value) -> Result<&mut Map<String, Value>> {
+    let obj = obj
+        .as_object_mut()
+        .ok_or_else(|| Failure::bad_request("object must be a JSON object"))?;
+    obj.entry("metadata")
+        .or_insert_with(|| Value::Object(Map::new()))
+        .as_object_mut()
+        .ok_or_else(|| Failure::bad_request("metadata must be a JSON object"))
+}
+
+/// Returns the object without its `metadata` and `status`, to decide whether the generation should change
+fn spec_of(obj: &Value) -> Value {
+    let mut obj = obj.clone();
+    if let Some(obj) = obj.as_object_mut() {
+        obj.remove("metadata");
+        obj.remove("status");
+    }
+    obj
+}
+
+/// Applies an RFC 7386 JSON merge patch
+fn merge_patch(target: &mut Value, patch: &Value) {
+    match patch {
+        Value::Object(patch) => {
+            if !target.is_object() {
+                *target = Value::Object(Map::new());
+            }
+            let target = target.as_object_mut().expect("target was just made an object");
+            for (key, value) in patch {
+                if value.is_null() {
+                    target.remove(key);
+                } else {
+                    merge_patch(target.entry(key.as_str()).or_insert(Value::Null), value);
+                }
+            }
+        }
+        patch => *target = patch.clone(),
+    }
+}
+
+fn is_dry_run(query: &HashMap<String, String>) -> bool {
+    query.get("dryRun").map_or(false, |dry_run| dry_run == "All")
+}
+
+fn json_body(body: &[u8]) -> Result<Value> {
+    serde_json::from_slice(body).map_err(|err| Failure::bad_request(format!("invalid JSON body: {}", err)))
+}
+
+impl State {
+    fn handle(&mut self, parts: &http::request::Parts, body: &[u8]) -> Result<Response<Body>> {
+        let target = parse_path(parts.uri.path()).ok_or_else(|| {
+            Failure::new(
+                StatusCode::NOT_FOUND,
+                "NotFound",
+                format!(
+                    "the server could not find the requested resource ({})",
+                    parts.uri.path()
+                ),
+            )
+        })?;
+        let query: HashMap<String, String> = parts
+            .uri
+            .query()
+            .map(|query| {
+                url::form_urlencoded::parse(query.as_bytes())
+                    .into_owned()
+                    .collect()
+            })
+            .unwrap_or_default();
+        if !matches!(target.subresource.as_deref(), None | Some("status")) {
+            return Err(Failure::new(
+                StatusCode::NOT_FOUND,
+                "NotFound",
+                format!("subresource {:?} is not supported", target.subresource),
+            ));
+        }
+        let name = target.name.clone();
+        let ok = |value: Value| Ok(json_response(StatusCode::OK, &value));
+        match (&parts.method, name) {
+            (&Method::GET, Some(name)) => ok(self.get(&target, &name)?),
+            (&Method::GET, None) if query.get("watch").map_or(false, |watch| watch == "true") => {
+                self.watch(&target, &query)
+            }
+            (&Method::GET, None) => ok(self.list(&target, &query)?),
+            (&Method::POST, None) => {
+                let obj = self.create(&target, json_body(body)?, false, is_dry_run(&query))?;
+                Ok(json_response(StatusCode::CREATED, &obj))
+            }
+            (&Method::PUT, Some(name)) => {
+                ok(self.replace(&target, &name, json_body(body)?, is_dry_run(&query))?)
+            }
+            (&Method::PATCH, Some(name)) => {
+                let content_type = parts
+                    .headers
+                    .get(http::header::CONTENT_TYPE)
+                    .and_then(|ct| ct.to_str().ok())
+                    .unwrap_or_default();
+                ok(self.patch(&target, &name, content_type, json_body(body)?, is_dry_run(&query))?)
+            }
+            (&Method::DELETE, name) => {
+                let params = if body.is_empty() {
+                    json!({})
+                } else {
+                    json_body(body)?
+                };
+                let dry_run =
+                    is_dry_run(&query) || params["dryRun"].as_array().map_or(false, |d| !d.is_empty());
+                match name {
+                    Some(name) => ok(self.delete(&target, &name, &params["preconditions"], dry_run)?),
+                    None => ok(self.delete_collection(&target, &query, dry_run)?),
+                }
+            }
+            (method, _) => Err(Failure::new(
+                StatusCode::METHOD_NOT_ALLOWED,
+                "MethodNotAllowed",
+                format!("method {} is not supported on {}", method, parts.uri.path()),
+            )),
+        }
+    }
+
+    fn collection(&mut self, target: &Target) -> &mut Collection {
+        self.collections.entry(target.collection.clone()).or_default()
+    }
+
+    fn key(target: &Target, name: &str) -> (Option<String>, String) {
+        (target.namespace.clone(), name.to_string())
+    }
+
+    fn get(&mut self, target: &Target, name: &str) -> Result<Value> {
+        self.collection(target)
+            .objects
+            .get(&Self::key(target, name))
+            .cloned()
+            .ok_or_else(|| Failure::not_found(target, name))
+    }
+
+    fn list(&mut self, target: &Target, query: &HashMap<String, String>) -> Result<Value> {
+        let filter = Filter::from_query(target, query)?;
+        // Continue tokens are `{offset}/{resourceVersion of the first page}`, so that they can expire
+        let (offset, list_version) = match query.get("continue") {
+            Some(token) => {
+                let mut parts = token.splitn(2, '/').map(str::parse::<u64>);
+                match (parts.next(), parts.next()) {
+                    (Some(Ok(offset)), Some(Ok(list_version))) => (offset as usize, list_version),
+                    _ => return Err(Failure::bad_request("invalid continue token")),
+                }
+            }
+            None => (0, self.resource_version),
+        };
+        if list_version < self.compacted {
+            return Err(Failure::new(
+                StatusCode::GONE,
+                "Expired",
+                "The provided continue parameter is too old to display a consistent list result. You can start a new list without the continue parameter.",
+            ));
+        }
+        let limit = match query.get("limit") {
+            Some(limit) => limit
+                .parse::<usize>()
+                .map_err(|_| Failure::bad_request("invalid limit"))?,
+            None => 0,
+        };
+        let resource_version = self.resource_version.to_string();
+        let mut items = self
+            .collection(target)
+            .objects
+            .values()
+            .filter(|obj| filter.matches(obj))
+            .skip(offset)
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut metadata = json!({ "resourceVersion": resource_version });
+        if limit > 0 && items.len() > limit {
+            items.truncate(limit);
+            metadata["continue"] = json!(format!("{}/{}", offset + limit, list_version));
+        }
+        Ok(json!({
+            "apiVersion": "v1",
+            "kind": "List",
+            "metadata": metadata,
+            "items": items,
+        }))
+    }
+
+    fn watch(&mut self, target: &Target, query: &HashMap<String, String>) -> Result<Response<Body>> {
+        let filter = Filter::from_query(target, query)?;
+        let (sender, receiver) = mpsc::unbounded();
+        let timeout = match query.get("timeoutSeconds") {
+            Some(timeout) => timeout
+                .parse::<u64>()
+                .map_err(|_| Failure::bad_request("invalid timeoutSeconds"))?,
+            None => 1800,
+        };
+        let watch = Watch { filter, sender };
+        let compacted = self.compacted;
+        let resource_version = self.resource_version;
+        let collection = self.collection(target);
+        match query.get("resourceVersion").map(String::as_str) {
+            None | Some("") | Some("0") => {
+                for obj in collection
+                    .objects
+                    .values()
+                    .filter(|obj| watch.filter.matches(obj))
+                {
+                    watch.send(&json!({ "type": "ADDED", "object": obj }));
+                }
+                if query.get("sendInitialEvents").map(String::as_str) == Some("true") {
+                    let kind = collection.objects.values().next().map(|obj| obj["kind"].clone());
+                    watch.send(&json!({
+                        "type": "BOOKMARK",
+                        "object": {
+                            "apiVersion": target.api_version,
+                            "kind": kind.unwrap_or_else(|| json!("")),
+                            "metadata": {
+                                "resourceVersion": resource_version.to_string(),
+                                "annotations": { "k8s.io/initial-events-end": "true" },
+                            },
+                        },
+                    }));
+                }
+                collection.watches.push(watch);
+            }
+            Some(version) => {
+                let version = version
+                    .parse::<u64>()
+                    .map_err(|_| Failure::bad_request("invalid resourceVersion"))?;
+                if version < compacted {
+                    let gone = Failure::new(
+                        StatusCode::GONE,
+                        "Expired",
+                        format!("too old resource version: {} ({})", version, compacted),
+                    );
+                    watch.send(&json!({ "type": "ERROR", "object": gone.status() }));
+                } else {
+                    for change in collection.history.iter().filter(|c| c.resource_version > version) {
+                        if let Some(event) = watch.filter.event(change) {
+                            watch.send(&event);
+                        }
+                    }
+                    collection.watches.push(watch);
+                }
+            }
+        }
+        let body = receiver.take_until(tokio::time::sleep(Duration::from_secs(timeout)));
+        Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .body(Body::wrap_stream(body))
+            .expect("response should be valid"))
+    }
+
+    /// Records a change to an object, and notifies the relevant watches
+    fn commit(&mut self, target: &Target, old: Option<Value>, mut new: Value, deleted: bool) -> Value {
+        self.resource_version += 1;
+        let resource_version = self.resource_version;
+        new["metadata"]["resourceVersion"] = json!(resource_version.to_string());
+        let name = new["metadata"]["name"].as_str().unwrap_or_default().to_string();
+        let collection = self.collection(target);
+        if deleted {
+            collection.objects.remove(&Self::key(target, &name));
+        } else {
+            collection.objects.insert(Self::key(target, &name), new.clone());
+        }
+        let change = Change {
+            resource_version,
+            old,
+            new: new.clone(),
+            deleted,
+        };
+        collection.watches.retain(|watch| {
+            watch
+                .filter
+                .event(&change)
+                .map_or(!watch.sender.is_closed(), |event| watch.send(&event))
+        });
+        collection.history.push(change);
+        new
+    }
+
+    /// Stores a new object, dropping its `status` unless `keep_status` is set
+    fn create(&mut self, target: &Target, mut obj: Value, keep_status: bool, dry_run: bool) -> Result<Value> {
+        if !keep_status {
+            if let Some(obj) = obj.as_object_mut() {
+                obj.remove("status");
+            }
+        }
+        let next_id = self.resource_version + 1;
+        let meta = metadata_mut(&mut obj)?;
+        if meta.get("name").map_or(true, Value::is_null) {
+            let generate_name = meta
+                .get("generateName")
+                .and_then(Value::as_str)
+                .ok_or_else(|| Failure::bad_request("name or generateName is required"))?;
+            meta.insert("name".into(), json!(format!("{}{:05x}", generate_name, next_id)));
+        }
+        match (&target.namespace, meta.get("namespace").and_then(Value::as_str)) {
+            (Some(ns), Some(obj_ns)) if ns != obj_ns => {
+                return Err(Failure::bad_request(
+                    "the namespace of the provided object does not match the namespace sent on the request",
+                ));
+            }
+            (Some(ns), _) => {
+                meta.insert("namespace".into(), json!(ns));
+            }
+            (None, _) => {
+                meta.remove("namespace");
+            }
+        }
+        let name = meta["name"].as_str().unwrap_or_default().to_string();
+        meta.insert(
+            "uid".into(),
+            json!(format!("00000000-0000-4000-8000-{:012x}", next_id)),
+        );
+        meta.insert(
+            "creationTimestamp".into(),
+            json!(Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)),
+        );
+        meta.insert("generation".into(), json!(1));
+        meta.remove("deletionTimestamp");
+
+        if self
+            .collection(target)
+            .objects
+            .contains_key(&Self::key(target, &name))
+        {
+            return Err(Failure::new(
+                StatusCode::CONFLICT,
+                "AlreadyExists",
+                format!("{} \"{}\" already exists", target.plural, name),
+            ));
+        }
+        if dry_run {
+            return Ok(obj);
+        }
+        Ok(self.commit(target, None, obj, false))
+    }
+
+    /// Writes a new version of an existing object, keeping the fields that the request is not allowed to touch
+    fn update(&mut self, target: &Target, old: Value, mut new: Value, dry_run: bool) -> Result<Value> {
+        if new["metadata"]["name"] != old["metadata"]["name"] {
+            return Err(Failure::bad_request(
+                "the name of the object does not match the name on the URL",
+            ));
+        }
+        let mut merged = if target.subresource.as_deref() == Some("status") {
+            let mut merged = old.clone();
+            merged["status"] = new.get("status").cloned().unwrap_or(Value::Null);
+            merged
+        } else {
+            let meta = metadata_mut(&mut new)?;
+            for &field in SERVER_FIELDS {
+                match old["metadata"].get(field) {
+                    Some(value) => meta.insert(field.into(), value.clone()),
+                    None => meta.remove(field),
+                };
+            }
+            meta.insert("namespace".into(), old["metadata"]["namespace"].clone());
+            new["status"] = old.get("status").cloned().unwrap_or(Value::Null);
+            new
+        };
+        merged["metadata"]["resourceVersion"] = old["metadata"]["resourceVersion"].clone();
+        if let Some(obj) = merged.as_object_mut() {
+            obj.retain(|_, value| !value.is_null());
+            if let Some(meta) = obj.get_mut("metadata").and_then(Value::as_object_mut) {
+                meta.retain(|_, value| !value.is_null());
+            }
+        }
+        if merged == old {
+            return Ok(old);
+        }
+        if spec_of(&merged) != spec_of(&old) {
+            let generation = old["metadata"]["generation"].as_i64().unwrap_or_default();
+            merged["metadata"]["generation"] = json!(generation + 1);
+        }
+        if dry_run {
+            return Ok(merged);
+        }
+        let deleted = !merged["metadata"]["deletionTimestamp"].is_null()
+            && merged["metadata"]["finalizers"]
+                .as_array()
+                .map_or(true, Vec::is_empty);
+        Ok(self.commit(target, Some(old), merged, deleted))
+    }
+
+    fn replace(&mut self, target: &Target, name: &str, obj: Value, dry_run: bool) -> Result<Value> {
+        let old = self.get(target, name)?;
+        let resource_version = &obj["metadata"]["resourceVersion"];
+        if !resource_version.is_null() && resource_version != &old["metadata"]["resourceVersion"] {
+            return Err(Failure::conflict(target, name));
+        }
+        self.update(target, old, obj, dry_run)
+    }
+
+    fn patch(
+        &mut self,
+        target: &Target,
+        name: &str,
+        content_type: &str,
+        patch: Value,
+        dry_run: bool,
+    ) -> Result<Value> {
+        let is_apply = content_type == "application/apply-patch+yaml";
+        let old = match self.get(target, name) {
+            Ok(old) => old,
+            Err(_) if is_apply && target.subresource.is_none() => {
+                let mut obj = patch;
+                metadata_mut(&mut obj)?.insert("name".into(), json!(name));
+                return self.create(target, obj, false, dry_run);
+            }
+            Err(err) => return Err(err),
+        };
+        let mut new = old.clone();
+        match content_type {
+            "application/merge-patch+json" | "application/strategic-merge-patch+json" => {
+                let resource_version = &patch["metadata"]["resourceVersion"];
+                if !resource_version.is_null() && resource_version != &old["metadata"]["resourceVersion"] {
+                    return Err(Failure::conflict(target, name));
+                }
+                merge_patch(&mut new, &patch);
+            }
+            "application/apply-patch+yaml" => merge_patch(&mut new, &patch),
+            #[cfg(feature = "jsonpatch")]
+            "application/json-patch+json" => {
+                let patch: json_patch::Patch = serde_json::from_value(patch)
+                    .map_err(|err| Failure::bad_request(format!("invalid JSON patch: {}", err)))?;
+                json_patch::patch(&mut new, &patch).map_err(|err| {
+                    Failure::new(StatusCode::UNPROCESSABLE_ENTITY, "Invalid", err.to_string())
+                })?;
+            }
+            other => {
+                return Err(Failure::new(
+                    StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                    "UnsupportedMediaType",
+                    format!("the body of the request was in an unknown format: {}", other),
+                ));
+            }
+        }
+        // The patch may not touch the resourceVersion of the stored object
+        new["metadata"]["resourceVersion"] = old["metadata"]["resourceVersion"].clone();
+        self.update(target, old, new, dry_run)
+    }
+
+    fn delete(&mut self, target: &Target, name: &str, preconditions: &Value, dry_run: bool) -> Result<Value> {
+        let old = self.get(target, name)?;
+        for (precondition, field) in &[("uid", "uid"), ("resourceVersion", "resourceVersion")] {
+            let expected = &preconditions[precondition];
+            if !expected.is_null() && expected != &old["metadata"][field] {
+                return Err(Failure::conflict(target, name));
+            }
+        }
+        self.delete_object(target, old, dry_run)
+    }
+
+    fn delete_object(&mut self, target: &Target, old: Value, dry_run: bool) -> Result<Value> {
+        let has_finalizers = old["metadata"]["finalizers"]
+            .as_array()
+            .map_or(false, |finalizers| !finalizers.is_empty());
+        if has_finalizers && !old["metadata"]["deletionTimestamp"].is_null() {
+            // Deletion is already pending
+            return Ok(old);
+        }
+        let mut new = old.clone();
+        if has_finalizers {
+            new["metadata"]["deletionTimestamp"] =
+                json!(Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true));
+        }
+        if dry_run {
+            return Ok(new);
+        }
+        Ok(self.commit(target, Some(old), new, !has_finalizers))
+    }
+
+    fn delete_collection(
+        &mut self,
+        target: &Target,
+        query: &HashMap<String, String>,
+        dry_run: bool,
+    ) -> Result<Value> {
+        let filter = Filter::from_query(target, query)?;
+        let matching = self
+            .collection(target)
+            .objects
+            .values()
+            .filter(|obj| filter.matches(obj))
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut items = Vec::new();
+        for obj in matching {
+            // Use the object's own namespace, since the collection may span all namespaces
+            let obj_target = Target {
+                namespace: obj["metadata"]["namespace"].as_str().map(String::from),
+                collection: target.collection.clone(),
+                api_version: target.api_version.clone(),
+                plural: target.plural.clone(),
+                name: None,
+                subresource: None,
+            };
+            items.push(self.delete_object(&obj_target, obj, dry_run)?);
+        }
+        Ok(json!({
+            "apiVersion": "v1",
+            "kind": "List",
+            "metadata": { "resourceVersion": self.resource_version.to_string() },
+            "items": items,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FakeApiServer;
+    use crate::{
+        api::{
+            Api, DeleteParams, ListParams, Patch, PatchParams, PostParams, ResourceExt, WatchEvent,
+            WatchParams,
+        },
+        Error,
+    };
+    use futures::{StreamExt, TryStreamExt};
+    use k8s_openapi::api::core::v1::{ConfigMap, Pod, PodStatus};
+    use serde_json::json;
+
+    fn configmap(name: &str) -> ConfigMap {
+        serde_json::from_value(json!({
+            "metadata": { "name": name, "labels": { "app": "test" } },
+            "data": { "key": "value" },
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn fake_should_create_get_and_list() {
+        let server = FakeApiServer::new();
+        let cms: Api<ConfigMap> = Api::namespaced(server.client(), "ns");
+        let created = cms.create(&PostParams::default(), &configmap("a")).await.unwrap();
+        assert_eq!(created.namespace().as_deref(), Some("ns"));
+        assert!(created.uid().is_some());
+        assert_eq!(
+            cms.get("a").await.unwrap().resource_version(),
+            created.resource_version()
+        );
+
+        match cms.create(&PostParams::default(), &configmap("a")).await {
+            Err(Error::Api(err)) => assert_eq!((err.code, err.reason.as_str()), (409, "AlreadyExists")),
+            other => panic!("expected conflict, got {:?}", other),
+        }
+        match cms.get("missing").await {
+            Err(Error::Api(err)) => assert_eq!(err.code, 404),
+            other => panic!("expected not found, got {:?}", other),
+        }
+
+        cms.create(&PostParams::default(), &configmap("b")).await.unwrap();
+        let other_ns: Api<ConfigMap> = Api::namespaced(server.client(), "other");
+        other_ns
+            .create(&PostParams::default(), &configmap("c"))
+            .await
+            .unwrap();
+        let names =
+            |list: crate::api::ObjectList<ConfigMap>| list.iter().map(|cm| cm.name()).collect::<Vec<_>>();
+        assert_eq!(names(cms.list(&ListParams::default()).await.unwrap()), vec![
+            "a", "b"
+        ]);
+        assert_eq!(
+            names(
+                Api::<ConfigMap>::all(server.client())
+                    .list(&ListParams::default())
+                    .await
+                    .unwrap()
+            ),
+            vec!["a", "b", "c"]
+        );
+        let lp = ListParams::default()
+            .fields("metadata.name!=a")
+            .labels("app in (test)");
+        assert_eq!(names(cms.list(&lp).await.unwrap()), vec!["b"]);
+    }
+
+    #[tokio::test]
+    async fn fake_should_track_resource_version_and_generation() {
+        let server = FakeApiServer::new();
+        let cms: Api<ConfigMap> = Api::namespaced(server.client(), "ns");
+        let created = cms.create(&PostParams::default(), &configmap("a")).await.unwrap();
+
+        let mut changed = created.clone();
+        changed
+            .data
+            .as_mut()
+            .unwrap()
+            .insert("key".into(), "other".into());
+        let replaced = cms.replace("a", &PostParams::default(), &changed).await.unwrap();
+        assert_ne!(replaced.resource_version(), created.resource_version());
+        assert_eq!(replaced.metadata.generation, Some(2));
+        // The first write already used up the original resourceVersion
+        match cms.replace("a", &PostParams::default(), &changed).await {
+            Err(Error::Api(err)) => assert_eq!(err.code, 409),
+            other => panic!("expected conflict, got {:?}", other),
+        }
+
+        let patched = cms
+            .patch(
+                "a",
+                &PatchParams::default(),
+                &Patch::Merge(json!({ "metadata": { "labels": { "app": null } } })),
+            )
+            .await
+            .unwrap();
+        assert!(patched.labels().is_empty());
+        assert_eq!(patched.metadata.generation, Some(2));
+    }
+
+    #[tokio::test]
+    async fn fake_should_separate_status_from_main_resource() {
+        let server = FakeApiServer::new();
+        let pods: Api<Pod> = Api::namespaced(server.client(), "ns");
+        pods.patch(
+            "a",
+            &PatchParams::apply("test"),
+            &Patch::Apply(json!({ "apiVersion": "v1", "kind": "Pod", "metadata": { "name": "a" } })),
+        )
+        .await
+        .unwrap();
+        let status = json!({ "status": { "phase": "Running" } });
+        pods.patch("a", &PatchParams::default(), &Patch::Merge(&status))
+            .await
+            .unwrap();
+        assert_eq!(pods.get("a").await.unwrap().status, None);
+        let pod = pods
+            .patch_status("a", &PatchParams::default(), &Patch::Merge(&status))
+            .await
+            .unwrap();
+        assert_eq!(
+            pod.status,
+            Some(PodStatus {
+                phase: Some("Running".into()),
+                ..PodStatus::default()
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn fake_should_drop_status_on_create() {
+        let server = FakeApiServer::new();
+        let pods: Api<Pod> = Api::namespaced(server.client(), "ns");
+        let running = PodStatus {
+            phase: Some("Running".into()),
+            ..PodStatus::default()
+        };
+        let mut pod: Pod = serde_json::from_value(json!({ "metadata": { "name": "a" } })).unwrap();
+        pod.status = Some(running.clone());
+        let created = pods.create(&PostParams::default(), &pod).await.unwrap();
+        assert_eq!(created.status, None);
+        let applied = pods
+            .patch(
+                "b",
+                &PatchParams::apply("test"),
+                &Patch::Apply(json!({
+                    "apiVersion": "v1",
+                    "kind": "Pod",
+                    "metadata": { "name": "b" },
+                    "status": { "phase": "Running" },
+                })),
+            )
+            .await
+            .unwrap();
+        assert_eq!(applied.status, None);
+        pod.metadata.name = Some("c".into());
+        pod.metadata.namespace = Some("ns".into());
+        assert_eq!(server.insert(&pod).status, Some(running.clone()));
+        assert_eq!(pods.get("c").await.unwrap().status, Some(running));
+    }
+
+    #[tokio::test]
+    async fn fake_should_defer_deletion_until_finalized() {
+        let server = FakeApiServer::new();
+        let cms: Api<ConfigMap> = Api::namespaced(server.client(), "ns");
+        let mut cm = configmap("a");
+        cm.metadata.finalizers = Some(vec!["test".into()]);
+        cms.create(&PostParams::default(), &cm).await.unwrap();
+        cms.delete("a", &DeleteParams::default()).await.unwrap();
+        assert!(cms.get("a").await.unwrap().metadata.deletion_timestamp.is_some());
+
+        cms.patch(
+            "a",
+            &PatchParams::default(),
+            &Patch::Merge(json!({ "metadata": { "finalizers": null } })),
+        )
+        .await
+        .unwrap();
+        assert!(cms.get("a").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn fake_should_stream_watch_events() {
+        let server = FakeApiServer::new();
+        let cms: Api<ConfigMap> = Api::namespaced(server.client(), "ns");
+        let created = cms.create(&PostParams::default(), &configmap("a")).await.unwrap();
+        let version = created.resource_version().unwrap();
+        let wp = WatchParams::default().labels("app=test");
+        let events = cms.watch(&wp, &version).await.unwrap().boxed();
+
+        cms.create(&PostParams::default(), &configmap("b")).await.unwrap();
+        cms.patch(
+            "a",
+            &PatchParams::default(),
+            &Patch::Merge(json!({ "metadata": { "labels": { "app": "other" } } })),
+        )
+        .await
+        .unwrap();
+        cms.delete("b", &DeleteParams::default()).await.unwrap();
+        server.disconnect_watches();
+        let events = events
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|event| match event {
+                WatchEvent::Added(cm) => format!("added {}", cm.name()),
+                WatchEvent::Modified(cm) => format!("modified {}", cm.name()),
+                WatchEvent::Deleted(cm) => format!("deleted {}", cm.name()),
+                other => panic!("unexpected event {:?}", other),
+            })
+            .collect::<Vec<_>>();
+        // `a` is reported as deleted once it stops matching the selector
+        assert_eq!(events, vec!["added b", "deleted a", "deleted b"]);
+
+        server.compact();
+        let mut events = cms.watch(&wp, &version).await.unwrap().boxed();
+        match events.try_next().await.unwrap() {
+            Some(WatchEvent::Error(err)) => assert_eq!(err.code, 410),
+            other => panic!("expected 410 Gone, got {:?}", other),
+        }
+    }
+}
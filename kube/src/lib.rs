@@ -10,7 +10,7 @@
 //!
 //! ```rust,no_run
 //! use futures::{StreamExt, TryStreamExt};
-//! use kube::api::{Api, ResourceExt, ListParams, PostParams, WatchEvent};
+//! use kube::api::{Api, ResourceExt, PostParams, WatchEvent, WatchParams};
 //! use kube::Client;
 //! use k8s_openapi::api::core::v1::Pod;
 //!
@@ -46,10 +46,10 @@
 //!     let pod = pods.create(&PostParams::default(), &pod).await?;
 //!
 //!     // Start a watch call for pods matching our name
-//!     let lp = ListParams::default()
+//!     let wp = WatchParams::default()
 //!             .fields(&format!("metadata.name={}", "my-pod"))
 //!             .timeout(10);
-//!     let mut stream = pods.watch(&lp, "0").await?.boxed();
+//!     let mut stream = pods.watch(&wp, "0").await?.boxed();
 //!
 //!     // Observe the pods phase for 10 seconds
 //!     while let Some(status) = stream.try_next().await? {
@@ -90,6 +90,14 @@ pub mod client;
 pub mod config;
 pub mod service;
 
+#[cfg(feature = "cassette")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cassette")))]
+pub mod cassette;
+
+#[cfg(feature = "fake")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fake")))]
+pub mod fake;
+
 pub mod error;
 
 #[cfg(feature = "derive")]